@@ -1,11 +1,44 @@
-use std::collections::HashMap;
+use crate::browser::{self, Browser};
+use crate::completion;
+use crate::config::Config;
+use crate::document::{self, Container, ContainerMut, Pairs, Segment};
+use crate::journal::{self, Journal};
+use crate::jsonc::Source;
+use crate::lazy::LazyDocument;
+use crate::ndjson::LinesDocument;
+use crate::schema::Schema;
+use crate::replace::{self, Replace};
+use crate::secrets;
+use crate::stats::{self, Stats};
+use crate::templates::{self, Picker};
+use crate::watch::FileWatcher;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
 use serde_json::Result;
 
+/// 撤销历史最多保留的快照数，每个快照都是整个文档的副本
+const HISTORY_LIMIT: usize = 100;
+
 /// 三个主要屏幕，Main 显示已存在的值屏幕，Editing 显示创建屏幕，Exiting 退出提示
+/// Recovering 在启动时发现遗留的恢复日志时询问是否恢复，Command 是底部的 `:` 命令行
+/// Templates 是新建成员时的模板选择列表，Open 是打开文档的文件浏览器
+/// Changed 在文件被其他进程改写时询问重新加载、合并还是保留内存中的版本，Stats 是文档统计
+/// Replace 是批量查找替换对话框
 pub enum CurrentScreen {
     Main,
     Editing,
     Exiting,
+    Recovering,
+    Command,
+    Templates,
+    Open,
+    Changed,
+    Stats,
+    Replace,
 }
 
 /// 追踪用户当前正在输入的字段
@@ -14,12 +47,51 @@ pub enum CurrentlyEditing {
     Value,
 }
 
+/// 弹窗只输入一个字段时的用途，None 表示输入新的键值对
+pub enum Prompt {
+    Rename(usize), // 重命名第 n 个成员，只显示 Key 框
+    Insert(usize), // 在数组的第 n 个位置插入元素，只显示 Value 框
+    SortField,     // 输入对象数组的排序字段，只显示 Key 框
+    TemplateKey(Value), // 为选中的模板输入 key，只显示 Key 框
+}
+
 pub struct App {
     pub key_input: String,              // 当前编辑的json key
-    pub value_input: String,            // 当前编辑的 json value
-    pub pairs: HashMap<String, String>, // serde支持的序列化键值对表示
+    pub value_input: String,            // 当前编辑的 json value，可以包含换行
+    pub value_scroll: usize,            // Value 框从底部向上滚动的行数，0 表示跟随输入末尾
+    pub pairs: Pairs,                   // serde支持的序列化键值对表示
     pub current_screen: CurrentScreen,
     pub currently_editing: Option<CurrentlyEditing>, // 正在编辑的键值可选状态，当用户不是编辑时为None
+    pub input_path: Option<PathBuf>,                 // 命令行传入的文件，None 表示新建
+    pub history: Vec<Pairs>,                         // 每次修改后的快照，用于撤销/重做
+    pub history_index: usize,                        // 当前快照在 history 中的位置
+    pub dirty: bool,                                 // 自上次写入恢复日志以来是否有修改
+    pub recovered: Option<Journal>,                  // 启动时发现的遗留日志，等待用户确认
+    pub selected: usize,                             // 列表中选中行的下标
+    pub lazy: Option<LazyDocument>,                  // 大文件的只读延迟加载视图
+    pub records: Option<LinesDocument>,              // JSON Lines 模式下的全部记录
+    pub status: Option<String>,                      // 底栏显示的提示信息，下次按键时清除
+    pub source: Option<Source>,                      // 加载时的原文，保存时保留注释和格式
+    pub prompt: Option<Prompt>,                      // 弹窗的单字段输入用途
+    pub path: Vec<Segment>,                          // 列表当前所在的嵌套容器，空表示顶层对象
+    pub command_input: String,                       // `:` 命令行中输入的内容
+    pub completions: Vec<String>,                    // 上次 Tab 补全的候选项
+    pub saved_index: usize,                          // 最近一次写入文件时的快照位置
+    pub config: Config,                              // 用户配置
+    pub reveal_secrets: bool,                        // 是否显示机密值的明文
+    pub schema: Option<Rc<Schema>>,                  // `--schema` 指定的 JSON Schema，所有标签页共用
+    pub picker: Option<Picker>,                      // 模板选择列表
+    pub suggestion: usize,                           // 补全列表中选中的候选
+    pub accepted: Option<(String, Value)>,           // Value 框最近选用的候选，保存时据此决定值的类型
+    pub browser: Option<Browser>,                    // 文件浏览器的目录树
+    pub opening: Option<PathBuf>,                    // 在文件浏览器中选中、等待在新标签页打开的文件
+    watcher: Option<FileWatcher>,                    // 监听输入文件被外部改写
+    disk: Option<String>,                            // 最近一次读写时文件的内容，作为合并的基准
+    saved_pairs: Option<Pairs>,                      // 最近一次读写时文件中的文档，判断机密值能否从文件恢复
+    pub external: Option<String>,                    // 外部改写后尚未处理的文件内容
+    pub open_in_editor: bool,                        // 按下 Ctrl-E，等待主循环暂停界面并打开外部编辑器
+    pub stats: Option<Stats>,                        // 统计浮层显示的结果，打开时计算
+    pub replace: Option<Replace>,                    // 查找替换对话框的状态
 }
 
 impl App {
@@ -27,20 +99,667 @@ impl App {
         App {
             key_input: String::new(),
             value_input: String::new(),
-            pairs: HashMap::new(),
+            value_scroll: 0,
+            pairs: Pairs::new(),
             current_screen: CurrentScreen::Main,
             currently_editing: None,
+            input_path: None,
+            history: vec![Pairs::new()],
+            history_index: 0,
+            dirty: false,
+            recovered: None,
+            selected: 0,
+            lazy: None,
+            records: None,
+            status: None,
+            source: None,
+            prompt: None,
+            path: Vec::new(),
+            command_input: String::new(),
+            completions: Vec::new(),
+            saved_index: 0,
+            config: Config::default(),
+            reveal_secrets: false,
+            schema: None,
+            picker: None,
+            suggestion: 0,
+            accepted: None,
+            browser: None,
+            opening: None,
+            watcher: None,
+            disk: None,
+            saved_pairs: None,
+            external: None,
+            open_in_editor: false,
+            stats: None,
+            replace: None,
+        }
+    }
+
+    /// 从文件加载 json 对象（允许 JSONC 注释），文件不存在时视为新建
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let mut app = App::new();
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                // 无界面编辑不监听文件，也靠它在写入前发现外部改写
+                app.disk = Some(content.clone());
+                let (source, pairs) = Source::parse(content)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                app.pairs = pairs;
+                app.history = vec![app.pairs.clone()];
+                app.saved_pairs = Some(app.pairs.clone());
+                app.source = Some(source);
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        app.input_path = Some(path);
+        Ok(app)
+    }
+
+    /// 以只读的延迟加载模式打开大文件，不把整个文档读入内存
+    pub fn open_lazy(path: PathBuf) -> io::Result<Self> {
+        let mut app = App::new();
+        app.lazy = Some(LazyDocument::open(&path)?);
+        app.input_path = Some(path);
+        Ok(app)
+    }
+
+    /// 以 JSON Lines 模式打开文件，每行一条记录
+    pub fn open_ndjson(path: PathBuf) -> io::Result<Self> {
+        let mut app = App::new();
+        app.records = Some(LinesDocument::open(&path)?);
+        app.disk = fs::read_to_string(&path).ok();
+        app.input_path = Some(path);
+        Ok(app)
+    }
+
+    /// 记录文件当前的内容并开始监听外部改写，延迟加载的只读文档不监听
+    pub fn watch(&mut self) {
+        let Some(path) = &self.input_path else {
+            return;
+        };
+        if self.lazy.is_some() {
+            return;
+        }
+        self.disk = fs::read_to_string(path).ok();
+        match FileWatcher::new(path) {
+            Ok(watcher) => self.watcher = Some(watcher),
+            // 系统不支持时只在保存前检查
+            Err(_) => self.watcher = None,
+        }
+    }
+
+    /// 处理文件监听的通知：内容与最近一次读写时不同则记下新内容，在主屏幕时弹出询问
+    pub fn check_disk(&mut self) {
+        if !self.watcher.as_ref().is_some_and(FileWatcher::touched) {
+            return;
+        }
+        let Some(text) = self
+            .input_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return;
+        };
+        if Some(&text) == self.disk.as_ref() {
+            // 自己写入的或内容没有变化
+            self.external = None;
+            return;
+        }
+        self.external = Some(text);
+        if let CurrentScreen::Main = self.current_screen {
+            self.current_screen = CurrentScreen::Changed;
+        }
+    }
+
+    /// 丢弃内存中的修改，重新加载磁盘上的版本；重新加载本身可以撤销
+    pub fn reload_external(&mut self) -> std::result::Result<(), String> {
+        let text = self.external.clone().ok_or("The file has not changed on disk")?;
+        if let (Some(records), Some(path)) = (&mut self.records, &self.input_path) {
+            *records = LinesDocument::open(path).map_err(|err| err.to_string())?;
+            self.pairs.clear();
+            self.history = vec![self.pairs.clone()];
+            self.history_index = 0;
+            self.saved_index = 0;
+        } else {
+            let (source, pairs) = Source::parse(text.clone())?;
+            self.saved_pairs = Some(pairs.clone());
+            self.pairs = pairs;
+            self.source = Some(source);
+            self.record_history();
+            self.saved_index = self.history_index;
+        }
+        self.selected = 0;
+        self.clamp_path();
+        self.disk = Some(text);
+        self.external = None;
+        self.current_screen = CurrentScreen::Main;
+        self.status = Some(String::from("Reloaded from disk"));
+        Ok(())
+    }
+
+    /// 把磁盘上的改动三方合并进内存中的文档，冲突处保留内存中的值
+    pub fn merge_external(&mut self) -> std::result::Result<(), String> {
+        let text = self.external.clone().ok_or("The file has not changed on disk")?;
+        if self.records.is_some() {
+            return Err(String::from(
+                "Merging is not supported for JSON Lines files",
+            ));
+        }
+        let base = match &self.disk {
+            Some(disk) => Source::parse(disk.clone())?.1,
+            None => Pairs::new(),
+        };
+        let (source, theirs) = Source::parse(text.clone())?;
+        let mut conflicts = Vec::new();
+        self.pairs = document::merge(&base, &self.pairs, &theirs, &mut conflicts);
+        self.saved_pairs = Some(theirs);
+        // 以磁盘上的新内容为原文，保存时只改写合并后不同的部分
+        self.source = Some(source);
+        self.record_history();
+        self.saved_index = usize::MAX;
+        self.clamp_path();
+        self.disk = Some(text);
+        self.external = None;
+        self.current_screen = CurrentScreen::Main;
+        self.status = Some(if conflicts.is_empty() {
+            String::from("Merged the changes from disk")
+        } else {
+            format!("Merged; kept mine on conflict: {}", conflicts.join(", "))
+        });
+        Ok(())
+    }
+
+    /// 忽略磁盘上的改动，下次保存时覆盖它
+    pub fn keep_mine(&mut self) -> std::result::Result<(), String> {
+        let text = self.external.take().ok_or("The file has not changed on disk")?;
+        self.saved_pairs = Source::parse(text.clone()).ok().map(|(_, pairs)| pairs);
+        self.disk = Some(text);
+        self.saved_index = usize::MAX;
+        self.current_screen = CurrentScreen::Main;
+        self.status = Some(String::from("Keeping your version; saving will overwrite the file"));
+        Ok(())
+    }
+
+    /// 交给外部编辑器的文本和临时文件扩展名：弹窗中为正在编辑的输入框，主屏幕为选中的值
+    /// 字符串按原文编辑，其他值格式化为 json
+    pub fn editor_text(&self) -> Option<(String, &'static str)> {
+        match self.current_screen {
+            CurrentScreen::Editing => match self.currently_editing {
+                Some(CurrentlyEditing::Key) => Some((self.key_input.clone(), "txt")),
+                Some(CurrentlyEditing::Value) => Some((self.value_input.clone(), "txt")),
+                None => None,
+            },
+            CurrentScreen::Main if self.lazy.is_none() && !self.in_record_list() => {
+                match self.selected_pair()? {
+                    (_, Value::String(text)) => Some((text.clone(), "txt")),
+                    (_, value) => Some((serde_json::to_string_pretty(value).ok()? + "\n", "json")),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// 应用外部编辑器的结果；json 解析失败时不做任何修改
+    pub fn apply_editor_text(&mut self, mut text: String) {
+        let Some((original, extension)) = self.editor_text() else {
+            return;
+        };
+        // 编辑器通常会在末尾补一个换行
+        if !original.ends_with('\n') && text.ends_with('\n') {
+            text.pop();
+            if text.ends_with('\r') {
+                text.pop();
+            }
+        }
+        if text == original {
+            return;
+        }
+        if let CurrentScreen::Editing = self.current_screen {
+            match self.currently_editing {
+                Some(CurrentlyEditing::Key) => self.key_input = text,
+                _ => {
+                    self.value_input = text;
+                    self.value_scroll = 0;
+                }
+            }
+            self.suggestion = 0;
+            return;
+        }
+        let value = if extension == "json" {
+            // 子树中没改动的数字保持原样，这里不做数字规范化
+            match serde_json::from_str(&text) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.status = Some(format!("Invalid JSON from the editor, nothing changed: {err}"));
+                    return;
+                }
+            }
+        } else {
+            Value::String(text)
+        };
+        let path = self.selected_path();
+        match self.set_at(&path, value) {
+            Ok(()) => self.status = Some(format!("Updated {}", document::json_path(&path))),
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// 计算整个文档的统计并打开浮层，未显示机密值时重复值中不列出它们
+    pub fn show_stats(&mut self) {
+        let hidden: &[String] = if self.reveal_secrets {
+            &[]
+        } else {
+            &self.config.secret_patterns
+        };
+        self.stats = Some(stats::compute(&self.pairs, hidden));
+        self.current_screen = CurrentScreen::Stats;
+    }
+
+    /// 打开查找替换对话框，默认只在当前所在的容器内替换
+    pub fn start_replace(&mut self) {
+        self.replace = Some(Replace::new());
+        self.current_screen = CurrentScreen::Replace;
+    }
+
+    /// 查找条件变化后重新列出匹配项
+    pub fn search_replace(&mut self) {
+        if let Some(replace) = &mut self.replace {
+            replace.search(&self.pairs, &self.path);
+        }
+    }
+
+    /// 应用接受的替换，整体作为一次可撤销的修改
+    pub fn apply_replace(&mut self) {
+        let Some(replace) = self.replace.take() else {
+            return;
+        };
+        self.current_screen = CurrentScreen::Main;
+        let mut pairs = self.pairs.clone();
+        let (applied, skipped) = replace::apply(&mut pairs, &replace.matches);
+        if applied > 0 {
+            self.pairs = pairs;
+            self.clamp_path();
+            self.record_history();
+        }
+        self.status = Some(if skipped.is_empty() {
+            format!("Replaced {applied} occurrences")
+        } else {
+            format!(
+                "Replaced {applied} occurrences; skipped (key already exists): {}",
+                skipped.join(", ")
+            )
+        });
+    }
+
+    /// 打开文件浏览器，从当前文件所在的目录开始
+    pub fn start_browsing(&mut self) {
+        let root = self
+            .input_path
+            .as_ref()
+            .and_then(|path| fs::canonicalize(path).ok())
+            .and_then(|path| path.parent().map(PathBuf::from))
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+        let hidden = if self.reveal_secrets {
+            Vec::new()
+        } else {
+            self.config.secret_patterns.clone()
+        };
+        match Browser::open(root, hidden) {
+            Ok(found) => {
+                self.browser = Some(found);
+                self.current_screen = CurrentScreen::Open;
+            }
+            Err(err) => self.status = Some(format!("Cannot list directory: {err}")),
+        }
+    }
+
+    /// 文件浏览器中按下 Enter：目录展开或收起，文件交给标签页打开
+    pub fn choose_file(&mut self) {
+        let Some(found) = &mut self.browser else {
+            return;
+        };
+        let Some(entry) = found.current() else {
+            return;
+        };
+        if entry.is_dir {
+            if let Err(err) = found.toggle() {
+                self.status = Some(err.to_string());
+            }
+        } else if browser::is_preview_only(&entry.path) {
+            self.status = Some(format!("{} can only be previewed", entry.name()));
+        } else {
+            self.opening = Some(entry.path.clone());
+            self.browser = None;
+            self.current_screen = CurrentScreen::Main;
         }
     }
 
+    /// 是否处于 JSON Lines 的记录列表（没有打开任何记录）
+    pub fn in_record_list(&self) -> bool {
+        self.records.as_ref().is_some_and(|records| records.open.is_none())
+    }
+
+    /// 在结构化编辑器中打开选中的记录，撤销历史从该记录开始
+    pub fn open_record(&mut self) {
+        let Some(records) = &mut self.records else {
+            return;
+        };
+        match records.record(records.selected) {
+            Ok(record) => {
+                records.open = Some(records.selected);
+                self.pairs = record;
+                self.history = vec![self.pairs.clone()];
+                self.history_index = 0;
+                self.saved_index = 0;
+                self.selected = 0;
+                self.path.clear();
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// 把编辑结果写回记录列表并返回列表
+    pub fn close_record(&mut self) {
+        let Some(records) = &mut self.records else {
+            return;
+        };
+        if let Some(index) = records.open.take() {
+            self.path.clear();
+            records.update(index, std::mem::take(&mut self.pairs));
+            self.history = vec![Pairs::new()];
+            self.history_index = 0;
+            self.saved_index = 0;
+        }
+    }
+
+    /// 解码输入框中的转义序列后保存并回到主屏幕，转义有误时提示原因并保持弹窗打开
     pub fn save_key_value(&mut self) {
-        self.pairs
-            .insert(self.key_input.clone(), self.value_input.clone());
-        self.key_input = String::new();
-        self.value_input = String::new();
+        let key = match document::unescape(&self.key_input) {
+            Ok(key) => key,
+            Err(err) => {
+                self.status = Some(format!("Key: {err}"));
+                return;
+            }
+        };
+        let value = match document::unescape(&self.value_input) {
+            Ok(value) => value,
+            Err(err) => {
+                self.status = Some(format!("Value: {err}"));
+                return;
+            }
+        };
+        // 只有从补全列表选用、之后没再改动的候选（布尔值、数字、null）按 json 值保存，
+        // 手动输入的内容一律是字符串，不因兄弟对象里碰巧有相同的值而改变类型
+        let value = match self.accepted.take() {
+            Some((text, candidate)) if text == self.value_input => match candidate {
+                Value::String(_) => Value::String(value),
+                candidate => candidate,
+            },
+            _ => Value::String(value),
+        };
+        self.key_input.clear();
+        self.value_input.clear();
+        self.value_scroll = 0;
+        self.suggestion = 0;
+        self.currently_editing = None;
+        self.current_screen = CurrentScreen::Main;
+        self.insert_pair(key, value);
+    }
+
+    /// 插入或覆盖一个键值对并选中它，记录为一次可撤销的修改；在数组中则插入到选中元素之后
+    pub fn insert_pair(&mut self, key: String, value: Value) {
+        let after = self.selected().map_or(0, |index| index + 1);
+        self.selected = match self.current_mut() {
+            ContainerMut::Object(pairs) => {
+                pairs.insert(key.clone(), value);
+                // 选中刚保存的行
+                pairs.keys().position(|k| *k == key).unwrap_or(0)
+            }
+            ContainerMut::Array(items) => {
+                items.insert(after, value);
+                after
+            }
+        };
+        self.record_history();
+    }
+
+    /// 在当前数组的 index 位置插入元素并选中它
+    pub fn insert_element(&mut self, index: usize, value: Value) {
+        if let ContainerMut::Array(items) = self.current_mut() {
+            let index = index.min(items.len());
+            items.insert(index, value);
+            self.selected = index;
+            self.record_history();
+        }
+    }
+
+    /// 新建成员：有可用模板时先打开模板列表，否则直接打开空白的输入弹窗
+    pub fn start_new_entry(&mut self) {
+        let mut found = match templates::from_directory() {
+            Ok(found) => found,
+            Err(err) => {
+                self.status = Some(err);
+                Vec::new()
+            }
+        };
+        if let Some(schema) = &self.schema {
+            found.extend(templates::from_schema(schema, &self.path, &self.current()));
+        }
+        if found.is_empty() {
+            self.start_blank_entry();
+        } else {
+            self.picker = Some(Picker {
+                templates: found,
+                selected: 0,
+            });
+            self.current_screen = CurrentScreen::Templates;
+        }
+    }
+
+    /// 空白的新建弹窗：数组只需要输入值，对象输入键值对
+    fn start_blank_entry(&mut self) {
+        if self.is_array() {
+            self.start_insert(self.len());
+        } else {
+            self.current_screen = CurrentScreen::Editing;
+            self.currently_editing = Some(CurrentlyEditing::Key);
+        }
+    }
+
+    /// 使用模板列表中选中的一项：空白行打开输入弹窗，带 key 的模板直接插入，其余先输入 key
+    pub fn choose_template(&mut self) {
+        let Some(picker) = self.picker.take() else {
+            return;
+        };
+        self.current_screen = CurrentScreen::Main;
+        let Some(template) = picker.selected.checked_sub(1).map(|index| {
+            let mut templates = picker.templates;
+            templates.swap_remove(index)
+        }) else {
+            self.start_blank_entry();
+            return;
+        };
+        match template.key {
+            Some(key) => self.insert_pair(key, template.value),
+            None if self.is_array() => self.insert_pair(String::new(), template.value),
+            None => {
+                self.prompt = Some(Prompt::TemplateKey(template.value));
+                self.current_screen = CurrentScreen::Editing;
+                self.currently_editing = Some(CurrentlyEditing::Key);
+            }
+        }
+    }
+
+    /// 打开只有 Value 框的弹窗，保存时在数组的 index 位置插入
+    pub fn start_insert(&mut self, index: usize) {
+        if self.is_array() {
+            self.prompt = Some(Prompt::Insert(index));
+            self.current_screen = CurrentScreen::Editing;
+            self.currently_editing = Some(CurrentlyEditing::Value);
+        }
+    }
+
+    /// 开始重命名选中的成员，Key 框预先填入原来的 key
+    pub fn start_rename(&mut self) {
+        if self.is_array() {
+            self.status = Some(String::from("Array elements have no keys"));
+            return;
+        }
+        if let (Some(index), Some((key, _))) = (self.selected(), self.selected_pair()) {
+            // 预填的 key 以转义形式显示，原样确认时解码回同一个 key
+            self.key_input = document::escape_input(&key);
+            self.prompt = Some(Prompt::Rename(index));
+            self.current_screen = CurrentScreen::Editing;
+            self.currently_editing = Some(CurrentlyEditing::Key);
+        }
+    }
+
+    /// 打开只有 Key 框的弹窗，输入对象数组的排序字段
+    pub fn start_sort_by_field(&mut self) {
+        if self.is_array() {
+            self.prompt = Some(Prompt::SortField);
+            self.current_screen = CurrentScreen::Editing;
+            self.currently_editing = Some(CurrentlyEditing::Key);
+        }
+    }
+
+    /// 完成弹窗中的单字段输入
+    /// 与 save_key_value 相同，转义有误时保持弹窗打开
+    pub fn submit_prompt(&mut self) {
+        let input = match self.prompt {
+            Some(Prompt::Insert(_)) => &self.value_input,
+            _ => &self.key_input,
+        };
+        let text = match document::unescape(input) {
+            Ok(text) => text,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        self.key_input.clear();
+        self.value_input.clear();
+        self.value_scroll = 0;
+        self.currently_editing = None;
+        self.current_screen = CurrentScreen::Main;
+        match self.prompt.take() {
+            Some(Prompt::Rename(index)) => self.rename_key(index, text),
+            Some(Prompt::Insert(index)) => self.insert_element(index, Value::String(text)),
+            Some(Prompt::SortField) => self.sort_by_field(&text),
+            Some(Prompt::TemplateKey(value)) => self.insert_pair(text, value),
+            None => {}
+        }
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        self.prompt = None;
+        self.key_input.clear();
+        self.value_input.clear();
+        self.value_scroll = 0;
+        self.current_screen = CurrentScreen::Main;
         self.currently_editing = None;
     }
 
+    /// 只修改 key，成员的位置和值不变；新 key 与其他成员重复时拒绝
+    fn rename_key(&mut self, index: usize, new_key: String) {
+        let Some(segment) = self.current().segment(index) else {
+            return;
+        };
+        let mut path = self.path.clone();
+        path.push(segment);
+        if let Err(err) = self.rename_at(&path, new_key) {
+            self.status = Some(err);
+        }
+    }
+
+    /// 把选中的成员上移或下移一位，选中行跟随移动
+    pub fn move_selected(&mut self, down: bool) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let target = if down {
+            index + 1
+        } else {
+            match index.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            }
+        };
+        if target >= self.len() {
+            return;
+        }
+        match self.current_mut() {
+            ContainerMut::Object(pairs) => document::swap_entries(pairs, index, target),
+            ContainerMut::Array(items) => items.swap(index, target),
+        }
+        self.selected = target;
+        self.record_history();
+    }
+
+    /// 删除选中的成员或元素，其余成员保持原有顺序
+    pub fn delete_selected(&mut self) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        match self.current_mut() {
+            ContainerMut::Object(pairs) => {
+                let key = pairs.keys().nth(index).cloned();
+                if let Some(key) = key {
+                    pairs.shift_remove(&key);
+                }
+            }
+            ContainerMut::Array(items) => {
+                items.remove(index);
+            }
+        }
+        self.record_history();
+    }
+
+    /// 复制选中的数组元素，副本插在原元素之后
+    pub fn duplicate_selected(&mut self) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        match self.current_mut() {
+            ContainerMut::Array(items) => {
+                let copy = items[index].clone();
+                items.insert(index + 1, copy);
+            }
+            ContainerMut::Object(_) => {
+                self.status = Some(String::from("Only array elements can be duplicated"));
+                return;
+            }
+        }
+        self.selected = index + 1;
+        self.record_history();
+    }
+
+    /// 排序当前容器：对象按 key，数组按元素的值
+    pub fn sort_current(&mut self) {
+        match self.current_mut() {
+            ContainerMut::Object(pairs) => {
+                let mut entries: Vec<(String, Value)> = std::mem::take(pairs).into_iter().collect();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                *pairs = entries.into_iter().collect();
+            }
+            ContainerMut::Array(items) => items.sort_by(document::compare_values),
+        }
+        self.selected = 0;
+        self.record_history();
+    }
+
+    /// 按字段排序对象数组，缺少该字段的元素排在前面
+    pub fn sort_by_field(&mut self, field: &str) {
+        if let ContainerMut::Array(items) = self.current_mut() {
+            items.sort_by(|a, b| document::compare_by_field(a, b, field));
+            self.selected = 0;
+            self.record_history();
+        }
+    }
+
     pub fn toggle_editing(&mut self) {
         if let Some(edit_mode) = &self.currently_editing {
             match edit_mode {
@@ -52,10 +771,464 @@ impl App {
         }
     }
 
+    /// 键值对弹窗中正在编辑的输入框的补全候选，只保留以已输入内容开头的项；
+    /// 输入框为空时不给候选，Enter 和上下键保持原来的作用
+    pub fn suggestions(&self) -> Vec<(String, Value)> {
+        if self.prompt.is_some() {
+            return Vec::new();
+        }
+        let schema = self.schema.as_deref();
+        let (input, candidates) = match self.currently_editing {
+            Some(CurrentlyEditing::Key) => (
+                &self.key_input,
+                completion::keys(schema, &self.pairs, &self.path)
+                    .into_iter()
+                    .map(|name| (document::escape_input(&name), Value::String(name)))
+                    .collect(),
+            ),
+            Some(CurrentlyEditing::Value) => {
+                let key = document::unescape(&self.key_input).unwrap_or_default();
+                let hidden: &[String] = if self.reveal_secrets {
+                    &[]
+                } else {
+                    &self.config.secret_patterns
+                };
+                let values = completion::values(schema, &self.pairs, &self.path, &key, hidden);
+                (&self.value_input, values)
+            }
+            None => return Vec::new(),
+        };
+        if input.is_empty() {
+            return Vec::new();
+        }
+        candidates
+            .into_iter()
+            .filter(|(text, _)| text.starts_with(input.as_str()) && text != input)
+            .collect()
+    }
+
+    /// 在补全列表中上下移动
+    pub fn move_suggestion(&mut self, down: bool) {
+        let len = self.suggestions().len();
+        if len > 0 {
+            self.suggestion = (self.suggestion % len + if down { 1 } else { len - 1 }) % len;
+        }
+    }
+
+    /// 用选中的候选替换输入框内容，没有候选时返回 false
+    pub fn accept_suggestion(&mut self) -> bool {
+        let mut suggestions = self.suggestions();
+        if suggestions.is_empty() {
+            return false;
+        }
+        let (text, candidate) = suggestions.swap_remove(self.suggestion % suggestions.len());
+        match self.currently_editing {
+            Some(CurrentlyEditing::Key) => self.key_input = text,
+            _ => {
+                self.value_input = text.clone();
+                self.value_scroll = 0;
+                self.accepted = Some((text, candidate));
+            }
+        }
+        self.suggestion = 0;
+        true
+    }
+
+    /// 列表当前显示的容器，路径失效（例如撤销后）时回到顶层对象
+    pub fn current(&self) -> Container<'_> {
+        document::container(&self.pairs, &self.path).unwrap_or(Container::Object(&self.pairs))
+    }
+
+    fn current_mut(&mut self) -> ContainerMut<'_> {
+        let path = match document::container(&self.pairs, &self.path) {
+            Some(_) => &self.path[..],
+            None => &[],
+        };
+        document::container_mut(&mut self.pairs, path).expect("empty path is the root object")
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self.current(), Container::Array(_))
+    }
+
+    fn len(&self) -> usize {
+        self.current().len()
+    }
+
+    /// 当前容器的行：对象为 key，数组为下标，顺序与文件一致
+    pub fn entries(&self) -> Vec<(String, &Value)> {
+        self.current().entries()
+    }
+
+    /// 选中行的 key（数组为下标）和值
+    pub fn selected_pair(&self) -> Option<(String, &Value)> {
+        self.selected().and_then(|index| self.current().get(index))
+    }
+
+    /// 选中行的完整路径
+    pub fn selected_path(&self) -> Vec<Segment> {
+        let mut path = self.path.clone();
+        if let Some(segment) = self.selected().and_then(|index| self.current().segment(index)) {
+            path.push(segment);
+        }
+        path
+    }
+
+    /// 当前选中行，列表为空时为 None
+    pub fn selected(&self) -> Option<usize> {
+        let len = self.len();
+        if len == 0 {
+            None
+        } else {
+            Some(self.selected.min(len - 1))
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected().unwrap_or(0).saturating_sub(1);
+    }
+
+    /// 进入选中的对象或数组
+    pub fn enter(&mut self) {
+        let path = self.selected_path();
+        if path.len() > self.path.len() && document::container(&self.pairs, &path).is_some() {
+            self.path = path;
+            self.selected = 0;
+        }
+    }
+
+    /// 回到上一层容器，并选中刚才所在的成员
+    pub fn leave(&mut self) {
+        let Some(segment) = self.path.pop() else {
+            return;
+        };
+        self.selected = match (&segment, self.current()) {
+            (Segment::Key(key), Container::Object(pairs)) => {
+                pairs.keys().position(|k| k == key).unwrap_or(0)
+            }
+            (Segment::Index(index), _) => *index,
+            _ => 0,
+        };
+    }
+
+    /// 把路径指向的位置设为 value，记录为一次可撤销的修改
+    pub fn set_at(&mut self, path: &[Segment], value: Value) -> std::result::Result<(), String> {
+        document::set_path(&mut self.pairs, path, value)?;
+        self.record_history();
+        Ok(())
+    }
+
+    /// 修改路径指向的成员的 key，新 key 与原来相同时不算修改
+    pub fn rename_at(&mut self, path: &[Segment], new_key: String) -> std::result::Result<(), String> {
+        if path.last() == Some(&Segment::Key(new_key.clone())) {
+            return Ok(());
+        }
+        document::rename_path(&mut self.pairs, path, new_key)?;
+        self.clamp_path();
+        self.record_history();
+        Ok(())
+    }
+
+    /// 删除路径指向的成员，当前所在的容器被删除时退回上层
+    pub fn delete_at(&mut self, path: &[Segment]) -> std::result::Result<(), String> {
+        document::delete_path(&mut self.pairs, path)?;
+        self.clamp_path();
+        self.record_history();
+        Ok(())
+    }
+
+    /// 列表和详情栏中显示的值：未选择显示明文时，机密 key 的值及嵌套的机密值显示为占位符
+    pub fn shown_value<'a>(&self, key: &str, value: &'a Value) -> Cow<'a, Value> {
+        let patterns = &self.config.secret_patterns;
+        if self.reveal_secrets {
+            return Cow::Borrowed(value);
+        }
+        // 所在的容器本身是机密值时，其中的所有内容都隐藏
+        let inside_secret = self
+            .path
+            .iter()
+            .any(|segment| matches!(segment, Segment::Key(key) if secrets::is_secret(key, patterns)));
+        if inside_secret || (!self.is_array() && secrets::is_secret(key, patterns)) {
+            return Cow::Owned(Value::String(secrets::MASK.to_string()));
+        }
+        secrets::mask(value, patterns)
+    }
+
+    pub fn toggle_secrets(&mut self) {
+        self.reveal_secrets = !self.reveal_secrets;
+        self.status = Some(String::from(if self.reveal_secrets {
+            "Secrets revealed"
+        } else {
+            "Secrets masked"
+        }));
+    }
+
+    /// 撤销或恢复后，当前路径可能已不存在，退回到仍然有效的最深一层
+    fn clamp_path(&mut self) {
+        while document::container(&self.pairs, &self.path).is_none() {
+            self.path.pop();
+        }
+    }
+
+    /// 修改后记录快照，丢弃当前位置之后的重做记录
+    fn record_history(&mut self) {
+        // 已保存的快照被新的修改覆盖后，不再有与文件一致的位置
+        if self.saved_index > self.history_index {
+            self.saved_index = usize::MAX;
+        }
+        self.history.truncate(self.history_index + 1);
+        self.history.push(self.pairs.clone());
+        // 超出上限时丢掉最早的快照，与文件一致的快照被丢掉后同样没有对应位置
+        let excess = self.history.len().saturating_sub(HISTORY_LIMIT);
+        if excess > 0 {
+            self.history.drain(..excess);
+            self.saved_index = match self.saved_index.checked_sub(excess) {
+                Some(index) if self.saved_index != usize::MAX => index,
+                _ => usize::MAX,
+            };
+        }
+        self.history_index = self.history.len() - 1;
+        self.dirty = true;
+    }
+
+    pub fn undo(&mut self) {
+        if self.history_index > 0 {
+            self.history_index -= 1;
+            self.pairs = self.history[self.history_index].clone();
+            self.clamp_path();
+            self.dirty = true;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.history_index + 1 < self.history.len() {
+            self.history_index += 1;
+            self.pairs = self.history[self.history_index].clone();
+            self.clamp_path();
+            self.dirty = true;
+        }
+    }
+
+    /// 只有普通文档使用恢复日志，延迟加载和 JSON Lines 模式不写日志
+    fn journaled(&self) -> bool {
+        self.lazy.is_none() && self.records.is_none()
+    }
+
+    /// 检查同一输入文件是否有上次未正常退出留下的日志，内容与当前文档不同时才提示恢复
+    pub fn check_recovery(&mut self) {
+        if !self.journaled() {
+            return;
+        }
+        // 未命名文档的日志按进程区分，先接管已退出的会话留下的日志
+        if self.input_path.is_none() {
+            let _ = journal::adopt_untitled();
+        }
+        let Some(path) = journal::journal_path(self.input_path.as_deref()) else {
+            return;
+        };
+        // 日志中的机密值是占位符，与当前文档比较前也要同样处理
+        match Journal::read(&path) {
+            Some(found)
+                if found.history_index > 0
+                    || found.pairs
+                        != self.journal_pairs(&self.pairs, found.masked, Some(&self.pairs)) =>
+            {
+                self.recovered = Some(found);
+                self.current_screen = CurrentScreen::Recovering;
+            }
+            _ => {
+                let _ = journal::remove(&path);
+            }
+        }
+    }
+
+    /// 用遗留日志中的文档和撤销历史替换当前状态
+    pub fn restore_journal(&mut self) {
+        if let Some(mut found) = self.recovered.take() {
+            self.path.clear();
+            // 日志中的机密值是占位符，从刚加载的原文件中取回；取不回的保留占位符，重新输入前不能保存
+            if found.masked {
+                let patterns = &self.config.secret_patterns;
+                let lost = secrets::unmask_pairs(&mut found.pairs, &self.pairs, patterns);
+                if !lost.is_empty() {
+                    self.status = Some(format!(
+                        "Secret values not recovered, re-enter them before saving: {}",
+                        lost.join(", ")
+                    ));
+                }
+            }
+            // 日志只有当前文档，恢复后可以撤销回文件中的版本
+            self.history = vec![std::mem::replace(&mut self.pairs, found.pairs)];
+            self.history.push(self.pairs.clone());
+            self.history_index = 1;
+            self.saved_index = 0;
+            self.dirty = true;
+        }
+    }
+
+    /// 写入日志的文档副本，masked 时机密值以占位符代替，saved 为文件中的版本
+    fn journal_pairs(&self, pairs: &Pairs, masked: bool, saved: Option<&Pairs>) -> Pairs {
+        let mut pairs = pairs.clone();
+        if masked {
+            secrets::mask_for_journal(&mut pairs, saved, &self.config.secret_patterns);
+        }
+        pairs
+    }
+
+    /// 放弃遗留日志
+    pub fn discard_journal(&mut self) -> io::Result<()> {
+        self.recovered = None;
+        match journal::journal_path(self.input_path.as_deref()) {
+            Some(path) => journal::remove(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// 把当前文档和撤销位置写入恢复日志
+    pub fn autosave(&mut self) -> io::Result<()> {
+        if !self.dirty || !self.journaled() {
+            return Ok(());
+        }
+        let Some(path) = journal::journal_path(self.input_path.as_deref()) else {
+            return Ok(());
+        };
+        // 除非配置允许，机密值不以明文写入日志
+        let masked = !self.config.journal_secrets;
+        let saved = self.saved_pairs.as_ref();
+        Journal {
+            input_path: self.input_path.clone(),
+            saved_at: journal::now(),
+            pairs: self.journal_pairs(&self.pairs, masked, saved),
+            history_index: self.history_index,
+            masked,
+        }
+        .write(&path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// 标签页标题：文件名，延迟加载和 JSON Lines 模式附带额外信息
+    pub fn title(&self) -> String {
+        let path = match (&self.lazy, &self.input_path) {
+            (Some(lazy), _) => {
+                return format!(
+                    "{} ({} MB, read-only)",
+                    lazy.path.display(),
+                    lazy.file_len / (1024 * 1024)
+                )
+            }
+            (None, Some(path)) => path.display().to_string(),
+            (None, None) => return String::from("Create New Json"),
+        };
+        match &self.records {
+            Some(records) => match records.open {
+                Some(index) => format!("{path} (JSON Lines, line {})", index + 1),
+                None => format!("{path} (JSON Lines, {} records)", records.len()),
+            },
+            None => path,
+        }
+    }
+
+    /// 是否有尚未保存的修改
+    pub fn is_modified(&self) -> bool {
+        self.history_index != self.saved_index || self.records.as_ref().is_some_and(|records| records.is_modified())
+    }
+
     pub fn print_json(&self) -> Result<()> {
+        // JSON Lines 模式直接写回文件，不输出到终端
+        if self.records.is_some() {
+            return Ok(());
+        }
         let output = serde_json::to_string(&self.pairs)?;
         println!("{}", output);
 
         Ok(())
     }
+
+    /// 立即写入文件，给出路径时改为写到该路径；写入后恢复日志不再需要
+    pub fn write(&mut self, path: Option<PathBuf>) -> io::Result<()> {
+        if path.is_some() {
+            // 另存到新文件时，旧的恢复日志与新路径无关
+            self.discard_journal()?;
+            self.input_path = path;
+            // 新文件与原来的监听和内容无关
+            self.external = None;
+            self.disk = None;
+            self.saved_pairs = None;
+        }
+        if self.input_path.is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no file name"));
+        }
+        self.save_file()?;
+        self.discard_journal()
+    }
+
+    /// 有输入文件时把文档写回该文件，不改动恢复日志；只读文档、文件已被外部改写
+    /// 或还有没恢复的机密值时拒绝写入
+    pub fn save_file(&mut self) -> io::Result<()> {
+        if self.lazy.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the document is open read-only",
+            ));
+        }
+        let Some(input_path) = &self.input_path else {
+            return Ok(());
+        };
+        // 没收到通知也再比较一次，避免覆盖其他进程写入的内容
+        if self.disk.is_some() && self.external.is_none() {
+            let text = fs::read_to_string(input_path).ok();
+            if text.is_some() && text != self.disk {
+                self.external = text;
+            }
+        }
+        if self.external.is_some() {
+            return Err(io::Error::other(
+                "the file changed on disk; use :reload, :merge or :keep first",
+            ));
+        }
+        // 恢复日志中没能还原的机密值只剩占位符，不能写进文件
+        let lost = secrets::placeholders(&self.pairs, &self.config.secret_patterns);
+        if !lost.is_empty() {
+            return Err(io::Error::other(format!(
+                "re-enter the secret values lost in recovery first: {}",
+                lost.join(", ")
+            )));
+        }
+        // JSON Lines 模式下正在编辑的记录也要一起写出
+        if let Some(records) = &mut self.records {
+            if let Some(index) = records.open {
+                records.update(index, self.pairs.clone());
+            }
+        }
+        let text = self.contents()?;
+        if let Some(path) = &self.input_path {
+            fs::write(path, &text)?;
+        }
+        self.saved_index = self.history_index;
+        self.saved_pairs = Some(self.pairs.clone());
+        if self.watcher.is_some() {
+            self.watch();
+        }
+        // 不管有没有监听，之后都和刚写入的内容比较，不把自己的写入当作外部改写
+        self.disk = Some(text);
+        Ok(())
+    }
+
+    /// 写入文件的内容，从文件加载的文档只改写被修改的部分
+    fn contents(&self) -> io::Result<String> {
+        match &self.records {
+            Some(records) => Ok(records.serialize()?),
+            None => match &self.source {
+                Some(source) => Ok(source.render(&self.pairs)),
+                None => Ok(serde_json::to_string_pretty(&self.pairs)? + "\n"),
+            },
+        }
+    }
 }
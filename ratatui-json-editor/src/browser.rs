@@ -0,0 +1,219 @@
+use crate::secrets;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// 文件浏览器列出的文件类型，其余文件不显示
+const EXTENSIONS: [&str; 7] = ["json", "jsonc", "ndjson", "jsonl", "yaml", "yml", "toml"];
+/// 只能预览、不能打开的格式
+const PREVIEW_ONLY: [&str; 3] = ["yaml", "yml", "toml"];
+/// 预览最多读取的字节数
+const PREVIEW_BYTES: u64 = 16 * 1024;
+
+/// 目录树中的一行
+pub struct Entry {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+}
+
+impl Entry {
+    /// 树中显示的名字，目录以 `/` 结尾
+    pub fn name(&self) -> String {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string());
+        if self.is_dir {
+            name + "/"
+        } else {
+            name
+        }
+    }
+}
+
+/// `CurrentScreen::Open` 的目录树，根目录下的成员深度为 0
+pub struct Browser {
+    pub root: PathBuf,
+    pub entries: Vec<Entry>,
+    pub selected: usize,
+    pub preview: String, // 选中项的预览，选中项改变时重新读取，不在每帧读文件
+    hidden: Vec<String>, // 预览中隐藏值的机密 key 模式，显示明文时为空
+}
+
+impl Browser {
+    pub fn open(root: PathBuf, hidden: Vec<String>) -> io::Result<Browser> {
+        let entries = children(&root, 0)?;
+        let mut browser = Browser {
+            root,
+            entries,
+            selected: 0,
+            preview: String::new(),
+            hidden,
+        };
+        browser.refresh_preview();
+        Ok(browser)
+    }
+
+    pub fn current(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1) % self.entries.len();
+            self.refresh_preview();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+            self.refresh_preview();
+        }
+    }
+
+    /// 展开或收起选中的目录
+    pub fn toggle(&mut self) -> io::Result<()> {
+        let Some(entry) = self.entries.get(self.selected) else {
+            return Ok(());
+        };
+        if !entry.is_dir {
+            return Ok(());
+        }
+        if entry.expanded {
+            self.collapse(self.selected);
+        } else {
+            let found = children(&entry.path, entry.depth + 1)?;
+            self.entries[self.selected].expanded = true;
+            let at = self.selected + 1;
+            self.entries.splice(at..at, found);
+        }
+        Ok(())
+    }
+
+    /// 收起展开的目录；否则选中上一级目录，已在最上层时把根目录换成它的父目录
+    pub fn leave(&mut self) -> io::Result<()> {
+        let Some(entry) = self.entries.get(self.selected) else {
+            return self.go_up();
+        };
+        if entry.expanded {
+            self.collapse(self.selected);
+            return Ok(());
+        }
+        let depth = entry.depth;
+        match self.entries[..self.selected]
+            .iter()
+            .rposition(|parent| parent.depth + 1 == depth)
+        {
+            Some(parent) => {
+                self.selected = parent;
+                self.refresh_preview();
+                Ok(())
+            }
+            None => self.go_up(),
+        }
+    }
+
+    fn go_up(&mut self) -> io::Result<()> {
+        let Some(parent) = self.root.parent().map(Path::to_path_buf) else {
+            return Ok(());
+        };
+        let hidden = self.hidden.clone();
+        let previous = std::mem::replace(self, Browser::open(parent, hidden)?);
+        // 选中原来的根目录
+        self.selected = self
+            .entries
+            .iter()
+            .position(|entry| entry.path == previous.root)
+            .unwrap_or(0);
+        self.refresh_preview();
+        Ok(())
+    }
+
+    fn collapse(&mut self, index: usize) {
+        let depth = self.entries[index].depth;
+        let end = self.entries[index + 1..]
+            .iter()
+            .position(|entry| entry.depth <= depth)
+            .map_or(self.entries.len(), |offset| index + 1 + offset);
+        self.entries.drain(index + 1..end);
+        self.entries[index].expanded = false;
+    }
+
+    fn refresh_preview(&mut self) {
+        self.preview = self.load_preview();
+    }
+
+    /// 选中项的预览：文件显示开头的内容（json 文件中机密 key 的值被隐藏），目录显示其中的成员
+    fn load_preview(&self) -> String {
+        let Some(entry) = self.current() else {
+            return String::from("No JSON files here");
+        };
+        if entry.is_dir {
+            return match children(&entry.path, 0) {
+                Ok(found) if found.is_empty() => String::from("No JSON files here"),
+                Ok(found) => found.iter().map(Entry::name).collect::<Vec<_>>().join("\n"),
+                Err(err) => err.to_string(),
+            };
+        }
+        let mut content = Vec::new();
+        let read = fs::File::open(&entry.path)
+            .and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut content));
+        match read {
+            Ok(_) if is_preview_only(&entry.path) => String::from_utf8_lossy(&content).into_owned(),
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&content);
+                secrets::mask_text(&text, &self.hidden).into_owned()
+            }
+            Err(err) => err.to_string(),
+        }
+    }
+}
+
+/// 只能预览的格式返回 true
+pub fn is_preview_only(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| PREVIEW_ONLY.contains(&ext))
+}
+
+/// 目录下不隐藏的子目录和支持的文件，目录在前，各自按名字排序
+fn children(dir: &Path, depth: usize) -> io::Result<Vec<Entry>> {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            dirs.push(path);
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| EXTENSIONS.contains(&ext))
+        {
+            files.push(path);
+        }
+    }
+    dirs.sort();
+    files.sort();
+    let entries = dirs
+        .into_iter()
+        .map(|path| (path, true))
+        .chain(files.into_iter().map(|path| (path, false)))
+        .map(|(path, is_dir)| Entry {
+            path,
+            depth,
+            is_dir,
+            expanded: false,
+        })
+        .collect();
+    Ok(entries)
+}
@@ -0,0 +1,160 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "usage: ratatui-json-editor [--lazy | --ndjson] [--schema SCHEMA] [FILE]...
+       ratatui-json-editor --replay REGISTER [--repeat N] FILE...
+       ratatui-json-editor [--set PATH=VALUE | --delete PATH | --rename PATH=KEY]... FILE [-o OUT]";
+
+/// 命令行参数
+#[derive(Default)]
+pub struct Args {
+    pub paths: Vec<PathBuf>,                // 要编辑的文件，每个文件一个标签页
+    pub lazy: bool,                         // 强制以只读的延迟加载模式打开
+    pub ndjson: bool,                       // 按 JSON Lines 逐行打开
+    pub replay: Option<char>,               // 不启动终端界面，直接回放保存的宏并写回文件
+    pub repeat: usize,                      // 宏回放的次数
+    pub edits: Vec<(&'static str, String)>, // 无界面执行的编辑命令和参数，按出现顺序
+    pub output: Option<PathBuf>,            // 编辑结果的输出文件，默认写回输入文件
+    pub schema: Option<PathBuf>,            // 用于生成新建模板的 JSON Schema
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Args, String> {
+        let mut parsed = Args {
+            repeat: 1,
+            ..Args::default()
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("--lazy") => parsed.lazy = true,
+                Some("--ndjson") => parsed.ndjson = true,
+                Some("--replay") => {
+                    let register = value(&mut args, "--replay")?;
+                    let mut chars = register.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if c.is_ascii_lowercase() => parsed.replay = Some(c),
+                        _ => {
+                            return Err(format!(
+                                "--replay expects a register a-z, got {register:?}"
+                            ))
+                        }
+                    }
+                }
+                Some("--repeat") => {
+                    let count = value(&mut args, "--repeat")?;
+                    parsed.repeat = count
+                        .parse()
+                        .map_err(|_| format!("--repeat expects a number, got {count:?}"))?;
+                }
+                Some("--set") => parsed.edits.push(("set", value(&mut args, "--set")?)),
+                Some("--delete") => parsed.edits.push(("delete", value(&mut args, "--delete")?)),
+                Some("--rename") => parsed.edits.push(("rename", value(&mut args, "--rename")?)),
+                Some("-o") | Some("--output") => {
+                    parsed.output = Some(PathBuf::from(value(&mut args, "-o")?));
+                }
+                Some("--schema") => {
+                    parsed.schema = Some(PathBuf::from(value(&mut args, "--schema")?));
+                }
+                Some("-h") | Some("--help") => return Err(USAGE.to_string()),
+                Some(flag) if flag.starts_with("--") => {
+                    return Err(format!("unknown option {flag}\n{USAGE}"))
+                }
+                _ => parsed.paths.push(PathBuf::from(arg)),
+            }
+        }
+        if parsed.replay.is_some() && parsed.paths.is_empty() {
+            return Err(format!("--replay needs at least one FILE\n{USAGE}"));
+        }
+        if parsed.replay.is_some() && parsed.lazy {
+            return Err(format!(
+                "--replay cannot be combined with --lazy, lazily loaded files are read-only\n{USAGE}"
+            ));
+        }
+        if !parsed.edits.is_empty() {
+            if parsed.replay.is_some() {
+                return Err(format!(
+                    "--replay cannot be combined with --set/--delete/--rename\n{USAGE}"
+                ));
+            }
+            if parsed.paths.len() != 1 {
+                return Err(format!(
+                    "--set/--delete/--rename need exactly one FILE\n{USAGE}"
+                ));
+            }
+        } else if parsed.output.is_some() {
+            return Err(format!(
+                "-o is only used with --set/--delete/--rename\n{USAGE}"
+            ));
+        }
+        Ok(parsed)
+    }
+}
+
+/// 取出选项后面的值
+fn value(args: &mut impl Iterator<Item = OsString>, flag: &str) -> Result<String, String> {
+    args.next()
+        .and_then(|arg| arg.into_string().ok())
+        .ok_or_else(|| format!("{flag} expects a value\n{USAGE}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn edits_keep_their_order_and_paths_are_passed_through() {
+        let args = parse(&[
+            "--set",
+            "arr[5]=1",
+            "--delete",
+            r#"$["x.y"]"#,
+            "--rename",
+            "a=b",
+            "in.json",
+            "-o",
+            "out.json",
+        ])
+        .unwrap();
+        let edits: Vec<(&str, &str)> = args
+            .edits
+            .iter()
+            .map(|(command, argument)| (*command, argument.as_str()))
+            .collect();
+        assert_eq!(
+            edits,
+            [
+                ("set", "arr[5]=1"),
+                ("delete", r#"$["x.y"]"#),
+                ("rename", "a=b")
+            ]
+        );
+        assert_eq!(args.paths, [PathBuf::from("in.json")]);
+        assert_eq!(args.output, Some(PathBuf::from("out.json")));
+    }
+
+    #[test]
+    fn rejects_conflicting_modes() {
+        assert!(parse(&["--set", "a=1"]).is_err());
+        assert!(parse(&["--set", "a=1", "a.json", "b.json"]).is_err());
+        assert!(parse(&["--set", "a=1", "--replay", "a", "a.json"]).is_err());
+        assert!(parse(&["-o", "out.json", "a.json"]).is_err());
+        assert!(parse(&["--replay", "a", "--lazy", "a.json"]).is_err());
+        assert!(parse(&["--replay", "A", "a.json"]).is_err());
+        assert!(parse(&["--replay", "a"]).is_err());
+        assert!(parse(&["--unknown", "a.json"]).is_err());
+    }
+
+    #[test]
+    fn replay_with_repeat() {
+        let args = parse(&["--replay", "q", "--repeat", "3", "a.json", "b.json"]).unwrap();
+        assert_eq!(args.replay, Some('q'));
+        assert_eq!(args.repeat, 3);
+        assert_eq!(args.paths.len(), 2);
+        assert!(parse(&["--replay", "q", "--repeat", "x", "a.json"]).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::app::App;
+use crate::document;
+use crate::schema;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// `:` 命令行支持的命令，补全时按此顺序列出
+pub const COMMANDS: [&str; 13] = [
+    "w", "q", "q!", "wq", "set", "delete", "rename", "sort", "format", "schema", "reload", "merge",
+    "keep",
+];
+
+/// 执行一行命令，返回 Some(do_print) 表示关闭当前标签页；出错时把原因写入状态栏
+pub fn execute(app: &mut App, line: &str) -> Option<bool> {
+    let line = line.trim();
+    let (name, argument) = match line.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (line, ""),
+    };
+    match run(app, name, argument) {
+        Ok(close) => close,
+        Err(err) => {
+            app.status = Some(err);
+            None
+        }
+    }
+}
+
+/// 执行一条命令，命令行和无界面的 `--set`/`--delete`/`--rename` 都经过这里
+pub fn run(app: &mut App, name: &str, argument: &str) -> Result<Option<bool>, String> {
+    match name {
+        "" => Ok(()),
+        "w" => write(app, argument),
+        // 与 vim 相同，有未保存的修改时 `:q` 拒绝关闭，`:q!` 放弃修改
+        "q" if app.is_modified() => Err(String::from(
+            "No write since last change (add ! to override)",
+        )),
+        "q" | "q!" => return Ok(Some(false)),
+        "wq" => {
+            write(app, argument)?;
+            return Ok(Some(false));
+        }
+        "set" => set(app, argument),
+        "delete" => document::parse_path(argument).and_then(|path| app.delete_at(&path)),
+        "rename" => rename(app, argument),
+        "sort" => {
+            if argument.is_empty() {
+                app.sort_current();
+            } else {
+                app.sort_by_field(argument);
+            }
+            Ok(())
+        }
+        "format" => {
+            // 放弃保留的原文格式，保存时整体格式化输出
+            app.source = None;
+            app.status = Some(String::from("The file will be written pretty-printed"));
+            Ok(())
+        }
+        "schema" => export_schema(app, argument),
+        // 文件被外部改写后：重新加载、三方合并或保留内存中的版本
+        "reload" => app.reload_external(),
+        "merge" => app.merge_external(),
+        "keep" => app.keep_mine(),
+        other => Err(format!("Unknown command: {other}")),
+    }?;
+    Ok(None)
+}
+
+fn write(app: &mut App, argument: &str) -> Result<(), String> {
+    let path = (!argument.is_empty()).then(|| PathBuf::from(argument));
+    app.write(path)
+        .map_err(|err| format!("Write failed: {err}"))?;
+    if let Some(path) = &app.input_path {
+        app.status = Some(format!("Written {}", path.display()));
+    }
+    Ok(())
+}
+
+/// `:set path=value`，值能解析为 json 时按 json 处理，否则作为字符串
+fn set(app: &mut App, argument: &str) -> Result<(), String> {
+    let (path, value) = argument.split_once('=').ok_or("usage: set path=value")?;
+    let path = document::parse_path(path)?;
+    let value = match serde_json::from_str(value) {
+        Ok(mut value) => {
+            document::normalize_numbers(&mut value);
+            value
+        }
+        Err(_) => Value::String(value.to_string()),
+    };
+    app.set_at(&path, value)
+}
+
+/// `:rename path=new_key`，只改 key，成员位置不变
+fn rename(app: &mut App, argument: &str) -> Result<(), String> {
+    let (path, new_key) = argument
+        .split_once('=')
+        .ok_or("usage: rename path=new_key")?;
+    let path = document::parse_path(path)?;
+    app.rename_at(&path, new_key.to_string())
+}
+
+/// `:schema FILE`，从当前文档推断 JSON Schema 写入文件；JSON Lines 文件以每条记录为示例
+fn export_schema(app: &mut App, argument: &str) -> Result<(), String> {
+    if argument.is_empty() {
+        return Err(String::from("usage: schema FILE"));
+    }
+    let samples: Vec<Value> = match &app.records {
+        Some(records) => (0..records.len())
+            .filter_map(|index| match records.open {
+                // 打开的记录以正在编辑的内容为准
+                Some(open) if open == index => Some(app.pairs.clone()),
+                _ => records.record(index).ok(),
+            })
+            .map(Value::Object)
+            .collect(),
+        None => vec![Value::Object(app.pairs.clone())],
+    };
+    let samples: Vec<&Value> = samples.iter().collect();
+    let inferred = schema::infer(&samples);
+    let content = serde_json::to_string_pretty(&inferred).map_err(|err| err.to_string())?;
+    fs::write(argument, content + "\n").map_err(|err| format!("{argument}: {err}"))?;
+    app.status = Some(format!("Schema written to {argument}"));
+    Ok(())
+}
+
+/// Tab 补全：第一个词补全命令名，`set`/`delete`/`rename` 的参数补全 json 路径
+/// 只有一个候选时直接补全，多个候选时补全到公共前缀并返回候选列表
+pub fn complete(app: &App, line: &str) -> (String, Vec<String>) {
+    let (prefix, word, candidates) = match line.split_once(' ') {
+        None => (
+            "",
+            line,
+            COMMANDS.iter().map(|name| name.to_string()).collect(),
+        ),
+        Some(("set" | "delete" | "rename", argument)) => {
+            let argument = argument.trim_start();
+            let start = line.len() - argument.len();
+            (&line[..start], argument, document::all_paths(&app.pairs))
+        }
+        Some(_) => return (line.to_string(), Vec::new()),
+    };
+    let matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect();
+    match matches.as_slice() {
+        [] => (line.to_string(), Vec::new()),
+        [only] => {
+            // 命令名补全后接一个空格，方便继续输入参数
+            let suffix = if prefix.is_empty() { " " } else { "" };
+            (format!("{prefix}{only}{suffix}"), Vec::new())
+        }
+        [first, rest @ ..] => {
+            let mut common = first.as_str();
+            for other in rest {
+                let len = common
+                    .char_indices()
+                    .zip(other.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(common.len().min(other.len()), |((index, _), _)| index);
+                common = &common[..len];
+            }
+            (format!("{prefix}{common}"), matches)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(json: &str) -> App {
+        let mut app = App::new();
+        app.pairs = serde_json::from_str(json).unwrap();
+        app.history = vec![app.pairs.clone()];
+        app
+    }
+
+    fn text(app: &App) -> String {
+        serde_json::to_string(&app.pairs).unwrap()
+    }
+
+    #[test]
+    fn set_parses_json_and_falls_back_to_string() {
+        let mut app = app(r#"{"a":1}"#);
+        run(&mut app, "set", "a=[1, 2]").unwrap();
+        run(&mut app, "set", "b.c=true").unwrap();
+        run(&mut app, "set", "name=hello world").unwrap();
+        assert_eq!(
+            text(&app),
+            r#"{"a":[1,2],"b":{"c":true},"name":"hello world"}"#
+        );
+    }
+
+    #[test]
+    fn set_array_index_appends_only_at_the_end() {
+        let mut app = app(r#"{"arr":[1,2]}"#);
+        run(&mut app, "set", "arr[0]=0").unwrap();
+        run(&mut app, "set", "arr[2]=3").unwrap();
+        assert!(run(&mut app, "set", "arr[5]=9").is_err());
+        assert_eq!(text(&app), r#"{"arr":[0,2,3]}"#);
+    }
+
+    #[test]
+    fn set_quoted_key_with_dots_and_escapes() {
+        let mut app = app("{}");
+        run(&mut app, "set", r#"$["x.y"]["say \"hi\""]=1"#).unwrap();
+        assert_eq!(text(&app), r#"{"x.y":{"say \"hi\"":1}}"#);
+    }
+
+    #[test]
+    fn delete_member_and_element() {
+        let mut app = app(r#"{"a":1,"tags":["x","y"],"b":2}"#);
+        run(&mut app, "delete", "tags[0]").unwrap();
+        run(&mut app, "delete", "a").unwrap();
+        assert!(run(&mut app, "delete", "missing").is_err());
+        assert!(run(&mut app, "delete", "tags[3]").is_err());
+        assert_eq!(text(&app), r#"{"tags":["y"],"b":2}"#);
+    }
+
+    #[test]
+    fn rename_keeps_position_and_rejects_duplicates() {
+        let mut app = app(r#"{"a":1,"b":{"c":2},"d":3}"#);
+        run(&mut app, "rename", "b.c=e").unwrap();
+        run(&mut app, "rename", "a=z").unwrap();
+        assert!(run(&mut app, "rename", "z=d").is_err());
+        assert!(run(&mut app, "rename", "missing=x").is_err());
+        assert_eq!(text(&app), r#"{"z":1,"b":{"e":2},"d":3}"#);
+    }
+
+    #[test]
+    fn quit_refuses_unsaved_changes_unless_forced() {
+        let mut app = app(r#"{"a":1}"#);
+        assert_eq!(run(&mut app, "q", ""), Ok(Some(false)));
+        run(&mut app, "set", "a=2").unwrap();
+        assert!(run(&mut app, "q", "").is_err());
+        assert_eq!(run(&mut app, "q!", ""), Ok(Some(false)));
+        run(&mut app, "delete", "a").unwrap();
+        app.undo();
+        app.undo();
+        assert_eq!(run(&mut app, "q", ""), Ok(Some(false)));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::document::{self, Container, Pairs, Segment};
+use crate::schema::{self, Schema};
+use crate::secrets;
+use serde_json::Value;
+
+/// 从同级对象推断取值时最多列出的候选数
+const MAX_INFERRED: usize = 8;
+
+/// Key 框的候选：schema 中当前对象尚未出现的属性名，没有 schema 时取同级对象中出现过的 key
+pub fn keys(schema: Option<&Schema>, pairs: &Pairs, path: &[Segment]) -> Vec<String> {
+    let Some(Container::Object(current)) = document::container(pairs, path) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = Vec::new();
+    let properties = schema
+        .and_then(|schema| schema.at(path))
+        .and_then(|node| node.get("properties"))
+        .and_then(Value::as_object);
+    match properties {
+        Some(properties) => names.extend(properties.keys().cloned()),
+        None => {
+            for sibling in siblings(pairs, path) {
+                for key in sibling.keys() {
+                    if !names.contains(key) {
+                        names.push(key.clone());
+                    }
+                }
+            }
+        }
+    }
+    names.retain(|name| !current.contains_key(name));
+    names
+}
+
+/// Value 框的候选：schema 中该属性的 enum/const 或布尔值，没有 schema 时取同级对象中该 key 的标量值
+/// 匹配 hidden 中模式的机密 key 不从同级对象推断，以免在下拉列表中露出其他条目的明文
+/// 返回输入框中的文本和保存时对应的 json 值
+pub fn values(
+    schema: Option<&Schema>,
+    pairs: &Pairs,
+    path: &[Segment],
+    key: &str,
+    hidden: &[String],
+) -> Vec<(String, Value)> {
+    let node = schema.and_then(|schema| {
+        let parent = schema.at(path)?;
+        Some(schema.resolve(schema::child(parent, &Segment::Key(key.to_string()))?))
+    });
+    let mut values: Vec<Value> = Vec::new();
+    match node {
+        Some(node) => {
+            if let Some(Value::Array(choices)) = node.get("enum") {
+                values.extend(choices.iter().cloned());
+            }
+            if let Some(constant) = node.get("const") {
+                values.push(constant.clone());
+            }
+            let boolean = match node.get("type") {
+                Some(Value::String(kind)) => kind == "boolean",
+                Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == "boolean"),
+                _ => false,
+            };
+            if boolean {
+                values.extend([Value::Bool(true), Value::Bool(false)]);
+            }
+        }
+        None if secrets::is_secret(key, hidden) => {}
+        None => {
+            for value in siblings(pairs, path).filter_map(|sibling| sibling.get(key)) {
+                let scalar = !value.is_object() && !value.is_array();
+                if scalar && !values.contains(value) && values.len() < MAX_INFERRED {
+                    values.push(value.clone());
+                }
+            }
+        }
+    }
+    values
+        .into_iter()
+        .filter(|value| !value.is_object() && !value.is_array())
+        .map(|value| {
+            // 字符串按输入框的转义写法显示，其余值显示 json 文本
+            let text = match &value {
+                Value::String(text) => document::escape_input(text),
+                other => other.to_string(),
+            };
+            (text, value)
+        })
+        .collect()
+}
+
+/// 当前对象在父容器中的同级对象，不包括它自己
+fn siblings<'a>(pairs: &'a Pairs, path: &[Segment]) -> impl Iterator<Item = &'a Pairs> {
+    let (parent, own) = match path.split_last() {
+        Some((last, parent)) => (document::container(pairs, parent), Some(last.clone())),
+        None => (None, None),
+    };
+    let members: Vec<(Segment, &Value)> = match parent {
+        Some(Container::Object(object)) => object
+            .iter()
+            .map(|(key, value)| (Segment::Key(key.clone()), value))
+            .collect(),
+        Some(Container::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (Segment::Index(index), value))
+            .collect(),
+        None => Vec::new(),
+    };
+    members
+        .into_iter()
+        .filter(move |(segment, _)| Some(segment) != own.as_ref())
+        .filter_map(|(_, value)| value.as_object())
+}
@@ -0,0 +1,49 @@
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 用户配置，读取自 `config.json`，缺少的字段使用默认值
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub secret_patterns: Vec<String>, // 值需要隐藏的 key 模式，`*` 匹配任意字符，不区分大小写
+    pub journal_secrets: bool,        // 是否把机密值以明文写入恢复日志
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            secret_patterns: ["*password*", "*token*", "*secret*"]
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            journal_secrets: false,
+        }
+    }
+}
+
+/// 配置目录，优先 `$XDG_CONFIG_HOME`，否则回退到 `~/.config`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("ratatui-json-editor"))
+}
+
+impl Config {
+    /// 读取配置文件，不存在时使用默认配置
+    pub fn load() -> Result<Config, String> {
+        let Some(path) = config_dir().map(|dir| dir.join("config.json")) else {
+            return Ok(Config::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                serde_json::from_str(&content).map_err(|err| format!("{}: {err}", path.display()))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(format!("{}: {err}", path.display())),
+        }
+    }
+}
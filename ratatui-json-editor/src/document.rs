@@ -0,0 +1,638 @@
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+/// 编辑器的文档：顶层 json 对象
+pub type Pairs = Map<String, Value>;
+
+/// 嵌套路径的一段：对象成员的 key 或数组下标
+#[derive(Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// 列表当前显示的容器
+pub enum Container<'a> {
+    Object(&'a Pairs),
+    Array(&'a Vec<Value>),
+}
+
+pub enum ContainerMut<'a> {
+    Object(&'a mut Pairs),
+    Array(&'a mut Vec<Value>),
+}
+
+impl<'a> Container<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            Container::Object(pairs) => pairs.len(),
+            Container::Array(items) => items.len(),
+        }
+    }
+
+    /// 列表中的每一行：对象显示 key，数组显示下标
+    pub fn entries(&self) -> Vec<(String, &'a Value)> {
+        match self {
+            Container::Object(pairs) => pairs
+                .iter()
+                .map(|(key, value)| (key.clone(), value))
+                .collect(),
+            Container::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<(String, &'a Value)> {
+        match self {
+            Container::Object(pairs) => pairs
+                .iter()
+                .nth(index)
+                .map(|(key, value)| (key.clone(), value)),
+            Container::Array(items) => items.get(index).map(|value| (index.to_string(), value)),
+        }
+    }
+
+    /// 第 index 行对应的路径段
+    pub fn segment(&self, index: usize) -> Option<Segment> {
+        match self {
+            Container::Object(pairs) => {
+                pairs.keys().nth(index).map(|key| Segment::Key(key.clone()))
+            }
+            Container::Array(items) => (index < items.len()).then_some(Segment::Index(index)),
+        }
+    }
+}
+
+/// 沿路径找到嵌套的对象或数组，空路径表示顶层对象；路径失效或指向标量时返回 None
+pub fn container<'a>(pairs: &'a Pairs, path: &[Segment]) -> Option<Container<'a>> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(Container::Object(pairs));
+    };
+    let Segment::Key(key) = first else {
+        return None;
+    };
+    let mut value = pairs.get(key)?;
+    for segment in rest {
+        value = match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map.get(key)?,
+            (Segment::Index(index), Value::Array(items)) => items.get(*index)?,
+            _ => return None,
+        };
+    }
+    match value {
+        Value::Object(map) => Some(Container::Object(map)),
+        Value::Array(items) => Some(Container::Array(items)),
+        _ => None,
+    }
+}
+
+pub fn container_mut<'a>(pairs: &'a mut Pairs, path: &[Segment]) -> Option<ContainerMut<'a>> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(ContainerMut::Object(pairs));
+    };
+    let Segment::Key(key) = first else {
+        return None;
+    };
+    let mut value = pairs.get_mut(key)?;
+    for segment in rest {
+        value = match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map.get_mut(key)?,
+            (Segment::Index(index), Value::Array(items)) => items.get_mut(*index)?,
+            _ => return None,
+        };
+    }
+    match value {
+        Value::Object(map) => Some(ContainerMut::Object(map)),
+        Value::Array(items) => Some(ContainerMut::Array(items)),
+        _ => None,
+    }
+}
+
+/// 与 container_mut 相同，但沿途缺少的对象成员会被创建为空对象
+fn container_creating<'a>(
+    pairs: &'a mut Pairs,
+    path: &[Segment],
+) -> Result<ContainerMut<'a>, String> {
+    let Some((first, rest)) = path.split_first() else {
+        return Ok(ContainerMut::Object(pairs));
+    };
+    let Segment::Key(key) = first else {
+        return Err(String::from("the document root is an object"));
+    };
+    let mut value = pairs
+        .entry(key.clone())
+        .or_insert_with(|| Value::Object(Pairs::new()));
+    for segment in rest {
+        value = match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Pairs::new())),
+            (Segment::Index(index), Value::Array(items)) => items
+                .get_mut(*index)
+                .ok_or_else(|| format!("index {index} is out of range"))?,
+            (segment, other) => return Err(mismatch(segment, other)),
+        };
+    }
+    match value {
+        Value::Object(map) => Ok(ContainerMut::Object(map)),
+        Value::Array(items) => Ok(ContainerMut::Array(items)),
+        other => Err(format!("{} has no members", type_name(other))),
+    }
+}
+
+fn mismatch(segment: &Segment, value: &Value) -> String {
+    match segment {
+        Segment::Key(key) => format!("{} has no key {key:?}", type_name(value)),
+        Segment::Index(index) => format!("{} has no index {index}", type_name(value)),
+    }
+}
+
+/// 把路径指向的位置设为 value，缺少的中间对象自动创建；数组下标等于长度时追加
+pub fn set_path(pairs: &mut Pairs, path: &[Segment], value: Value) -> Result<(), String> {
+    let (last, parents) = path.split_last().ok_or("the path is empty")?;
+    match (container_creating(pairs, parents)?, last) {
+        (ContainerMut::Object(map), Segment::Key(key)) => {
+            map.insert(key.clone(), value);
+        }
+        (ContainerMut::Array(items), Segment::Index(index)) if *index < items.len() => {
+            items[*index] = value;
+        }
+        (ContainerMut::Array(items), Segment::Index(index)) if *index == items.len() => {
+            items.push(value);
+        }
+        (ContainerMut::Array(_), Segment::Index(index)) => {
+            return Err(format!("index {index} is out of range"));
+        }
+        (ContainerMut::Object(_), Segment::Index(index)) => {
+            return Err(format!("object has no index {index}"));
+        }
+        (ContainerMut::Array(_), Segment::Key(key)) => {
+            return Err(format!("array has no key {key:?}"));
+        }
+    }
+    Ok(())
+}
+
+/// 修改路径指向的对象成员的 key，位置和值不变；新 key 与其他成员重复时拒绝
+pub fn rename_path(pairs: &mut Pairs, path: &[Segment], new_key: String) -> Result<(), String> {
+    let not_found = || format!("{} does not exist", json_path(path));
+    let Some((Segment::Key(key), parents)) = path.split_last() else {
+        return Err(format!("{} is not an object member", json_path(path)));
+    };
+    let Some(ContainerMut::Object(map)) = container_mut(pairs, parents) else {
+        return Err(not_found());
+    };
+    let index = map.keys().position(|k| k == key).ok_or_else(not_found)?;
+    match map.keys().position(|k| *k == new_key) {
+        Some(existing) if existing != index => Err(format!("Key {new_key} already exists")),
+        _ => {
+            rename_entry(map, index, new_key);
+            Ok(())
+        }
+    }
+}
+
+/// 删除路径指向的成员或元素，其余成员保持原有顺序
+pub fn delete_path(pairs: &mut Pairs, path: &[Segment]) -> Result<(), String> {
+    let (last, parents) = path.split_last().ok_or("the path is empty")?;
+    let not_found = || format!("{} does not exist", json_path(path));
+    match (container_mut(pairs, parents).ok_or_else(not_found)?, last) {
+        (ContainerMut::Object(map), Segment::Key(key)) => {
+            map.shift_remove(key).ok_or_else(not_found)?;
+        }
+        (ContainerMut::Array(items), Segment::Index(index)) if *index < items.len() => {
+            items.remove(*index);
+        }
+        _ => return Err(not_found()),
+    }
+    Ok(())
+}
+
+/// 解析 `a.b[0]`、`$.a["x y"]` 形式的路径，开头的 `$` 可以省略
+pub fn parse_path(text: &str) -> Result<Vec<Segment>, String> {
+    let mut rest = text.trim();
+    rest = rest.strip_prefix('$').unwrap_or(rest);
+    let mut path = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+        }
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = if after.starts_with('"') {
+                // 带引号的 key，跳过转义后的引号
+                let bytes = after.as_bytes();
+                let mut end = 1;
+                while end < bytes.len() && bytes[end] != b'"' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                end + 1
+            } else {
+                after.find(']').unwrap_or(after.len())
+            };
+            let inner = after
+                .get(..end)
+                .ok_or_else(|| format!("unterminated [ in {text:?}"))?;
+            if !after[end..].starts_with(']') {
+                return Err(format!("expected ] in {text:?}"));
+            }
+            path.push(if inner.starts_with('"') {
+                Segment::Key(
+                    serde_json::from_str(inner).map_err(|_| format!("invalid key {inner}"))?,
+                )
+            } else {
+                Segment::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| format!("invalid index [{inner}]"))?,
+                )
+            });
+            rest = &after[end + 1..];
+        } else {
+            // 不带引号的 key 不能含有 `]`，`a[1]b]` 这样的笔误报错而不是产生 key `b]`
+            let end = rest.find(['.', '[', ']']).unwrap_or(rest.len());
+            if rest[end..].starts_with(']') {
+                return Err(format!("unexpected ] in {text:?}"));
+            }
+            if end == 0 {
+                return Err(format!("empty key in {text:?}"));
+            }
+            path.push(Segment::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+    Ok(path)
+}
+
+/// 文档中所有成员的路径（不带开头的 `$`），用于补全
+pub fn all_paths(pairs: &Pairs) -> Vec<String> {
+    fn walk(value: &Value, path: &mut Vec<Segment>, out: &mut Vec<String>) {
+        let children: Vec<(Segment, &Value)> = match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| (Segment::Key(key.clone()), value))
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (Segment::Index(index), value))
+                .collect(),
+            _ => return,
+        };
+        for (segment, child) in children {
+            path.push(segment);
+            out.push(relative_path(path));
+            walk(child, path, out);
+            path.pop();
+        }
+    }
+    let mut out = Vec::new();
+    for (key, value) in pairs {
+        let mut path = vec![Segment::Key(key.clone())];
+        out.push(relative_path(&path));
+        walk(value, &mut path, &mut out);
+    }
+    out
+}
+
+/// 命令行中使用的路径写法，即去掉开头 `$` 的 json 路径
+pub fn relative_path(path: &[Segment]) -> String {
+    let text = json_path(path);
+    let text = &text[1..];
+    text.strip_prefix('.').unwrap_or(text).to_string()
+}
+
+/// 排序时的值比较：先按类型（null < boolean < number < string < array < object），同类型再比较内容
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.total_cmp(&b)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.to_string().cmp(&b.to_string())),
+    }
+}
+
+/// 按对象元素的某个字段比较，缺少该字段的元素排在前面
+pub fn compare_by_field(a: &Value, b: &Value, field: &str) -> Ordering {
+    match (a.get(field), b.get(field)) {
+        (Some(a), Some(b)) => compare_values(a, b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// 把第 index 个成员的 key 改为 new_key，位置和值保持不变
+pub fn rename_entry(pairs: &mut Pairs, index: usize, new_key: String) {
+    *pairs = std::mem::take(pairs)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (key, value))| {
+            if i == index {
+                (new_key.clone(), value)
+            } else {
+                (key, value)
+            }
+        })
+        .collect();
+}
+
+/// 三方合并：以上次读写文件时的 base 为基准，合并内存中的 mine 与磁盘上的 theirs
+/// 双方改动了同一处且结果不同时保留 mine，并把冲突的路径记入 conflicts
+pub fn merge(base: &Pairs, mine: &Pairs, theirs: &Pairs, conflicts: &mut Vec<String>) -> Pairs {
+    merge_object(base, mine, theirs, &mut Vec::new(), conflicts)
+}
+
+fn merge_object(
+    base: &Pairs,
+    mine: &Pairs,
+    theirs: &Pairs,
+    path: &mut Vec<Segment>,
+    conflicts: &mut Vec<String>,
+) -> Pairs {
+    // 按 mine 的顺序，theirs 新增的 key 排在后面
+    let keys: Vec<&String> = mine
+        .keys()
+        .chain(theirs.keys().filter(|key| !mine.contains_key(*key)))
+        .collect();
+    let mut merged = Pairs::new();
+    for key in keys {
+        path.push(Segment::Key(key.clone()));
+        let value = merge_value(
+            base.get(key),
+            mine.get(key),
+            theirs.get(key),
+            path,
+            conflicts,
+        );
+        path.pop();
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+    merged
+}
+
+/// None 表示该成员不存在（或被删除）
+fn merge_value(
+    base: Option<&Value>,
+    mine: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &mut Vec<Segment>,
+    conflicts: &mut Vec<String>,
+) -> Option<Value> {
+    if mine == theirs || theirs == base {
+        return mine.cloned();
+    }
+    if mine == base {
+        return theirs.cloned();
+    }
+    // 双方都改动了同一个对象时逐个成员合并
+    if let (Some(Value::Object(mine)), Some(Value::Object(theirs))) = (mine, theirs) {
+        let empty = Pairs::new();
+        let base = match base {
+            Some(Value::Object(base)) => base,
+            _ => &empty,
+        };
+        return Some(Value::Object(merge_object(
+            base, mine, theirs, path, conflicts,
+        )));
+    }
+    conflicts.push(json_path(path));
+    mine.cloned()
+}
+
+/// 交换相邻的两个成员
+pub fn swap_entries(pairs: &mut Pairs, a: usize, b: usize) {
+    let mut entries: Vec<(String, Value)> = std::mem::take(pairs).into_iter().collect();
+    entries.swap(a, b);
+    *pairs = entries.into_iter().collect();
+}
+
+/// 值的 json 类型名称
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// 值在列表中的单行表示：字符串显示原文（不可见字符转义），其他类型显示紧凑 json
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => escape_nonprintable(text),
+        other => other.to_string(),
+    }
+}
+
+/// 值在详情栏中的完整表示：字符串显示反转义后的文本（保留换行），对象和数组格式化输出
+pub fn preview_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text
+            .split('\n')
+            .map(escape_nonprintable)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+/// 控制字符和零宽、双向控制等不可见字符
+fn is_nonprintable(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+        )
+}
+
+/// 把不可见字符写成 json 转义形式，例如 `\n`、`\t`、`\u200b`，其他字符保持原样
+pub fn escape_nonprintable(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if is_nonprintable(c) => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 把文本写成输入框中的形式：反斜杠和不可见字符转义，unescape 的逆操作
+pub fn escape_input(text: &str) -> String {
+    escape_nonprintable(&text.replace('\\', "\\\\"))
+}
+
+/// 解码输入框中的转义序列（`\n`、`\t`、`\\`、`\"`、`\uXXXX` 等，与 json 字符串相同）
+/// 出错时返回错误所在的字符位置和原因
+pub fn unescape(text: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(text.len());
+    let mut chars = text.char_indices();
+    while let Some((position, c)) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        let escape = match chars.next() {
+            Some((_, escape)) => escape,
+            None => return Err(format!("trailing \\ at {position}")),
+        };
+        let c = match escape {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '\\' | '"' | '/' => escape,
+            'u' => {
+                let mut unit = hex4(&mut chars, position)?;
+                // 代理对：高位代理后面必须紧跟低位代理
+                if (0xD800..0xDC00).contains(&unit) {
+                    let low = match (chars.next(), chars.next()) {
+                        (Some((_, '\\')), Some((_, 'u'))) => hex4(&mut chars, position)?,
+                        _ => return Err(format!("unpaired surrogate \\u{unit:04x} at {position}")),
+                    };
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(format!("unpaired surrogate \\u{unit:04x} at {position}"));
+                    }
+                    unit = 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00);
+                }
+                char::from_u32(unit)
+                    .ok_or_else(|| format!("invalid code point \\u{unit:04x} at {position}"))?
+            }
+            other => return Err(format!("invalid escape \\{other} at {position}")),
+        };
+        decoded.push(c);
+    }
+    Ok(decoded)
+}
+
+fn hex4(chars: &mut impl Iterator<Item = (usize, char)>, position: usize) -> Result<u32, String> {
+    let digits: String = chars.take(4).map(|(_, c)| c).collect();
+    if digits.len() != 4 {
+        return Err(format!("\\u needs four hex digits at {position}"));
+    }
+    u32::from_str_radix(&digits, 16).map_err(|_| format!("invalid \\u{digits} at {position}"))
+}
+
+/// 用户编辑的值中的数字改写为标准形式；文件中未被编辑的数字保持原样（arbitrary_precision）
+pub fn normalize_numbers(value: &mut Value) {
+    match value {
+        Value::Number(number) => {
+            if let Some(normal) = normalized(number) {
+                *number = normal;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_numbers),
+        Value::Object(map) => map.values_mut().for_each(normalize_numbers),
+        _ => {}
+    }
+}
+
+/// 整数在 i64/u64 范围内、小数的有效数字不超过 15 位时才能无损改写，否则返回 None 保留原文
+fn normalized(number: &Number) -> Option<Number> {
+    let text = number.to_string();
+    if let Ok(integer) = text.parse::<i64>() {
+        return Some(integer.into());
+    }
+    if let Ok(integer) = text.parse::<u64>() {
+        return Some(integer.into());
+    }
+    let mantissa = text.split(['e', 'E']).next()?;
+    let digits = mantissa
+        .chars()
+        .filter(char::is_ascii_digit)
+        .skip_while(|c| *c == '0')
+        .count();
+    if digits > 15 {
+        return None;
+    }
+    Number::from_f64(text.parse().ok()?)
+}
+
+/// 值序列化为紧凑 json 后的字节数
+pub fn byte_size(value: &Value) -> usize {
+    serde_json::to_vec(value).map(|bytes| bytes.len()).unwrap_or(0)
+}
+
+/// 嵌套路径的 json 路径表示，标识符形式的 key 用 `.key`，其他用 `["key"]`，数组下标用 `[0]`
+pub fn json_path(path: &[Segment]) -> String {
+    let mut text = String::from("$");
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                let mut chars = key.chars();
+                let is_identifier = chars
+                    .next()
+                    .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+                    && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+                if is_identifier {
+                    text.push('.');
+                    text.push_str(key);
+                } else {
+                    text.push_str(&format!("[{}]", Value::String(key.to_string())));
+                }
+            }
+            Segment::Index(index) => text.push_str(&format!("[{index}]")),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> String {
+        json_path(&parse_path(text).unwrap())
+    }
+
+    #[test]
+    fn parse_keys_and_indexes() {
+        assert_eq!(parsed("arr[5]"), "$.arr[5]");
+        assert_eq!(parsed("$.a.b[0][12].c"), "$.a.b[0][12].c");
+        assert_eq!(parsed("  a  "), "$.a");
+        assert_eq!(parsed(""), "$");
+        assert!(matches!(parse_path("arr[5]").unwrap()[1], Segment::Index(5)));
+    }
+
+    #[test]
+    fn parse_quoted_keys() {
+        let path = parse_path(r#"$["x.y"]["a\"]b"]["back\\"][0]"#).unwrap();
+        assert!(path[0] == Segment::Key(String::from("x.y")));
+        assert!(path[1] == Segment::Key(String::from("a\"]b")));
+        assert!(path[2] == Segment::Key(String::from("back\\")));
+        assert!(path[3] == Segment::Index(0));
+        // 引号里的数字是 key，不是下标
+        assert!(parse_path(r#"a["5"]"#).unwrap()[1] == Segment::Key(String::from("5")));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_paths() {
+        for text in ["a[", "a[x]", "a[-1]", "a..b", "a[\"x]", "a[\"x\"", "a[1]b]"] {
+            assert!(parse_path(text).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 把文本写入临时文件，用 $EDITOR（未设置时用 vi）打开，编辑器退出后读回内容
+/// extension 决定临时文件的扩展名，便于编辑器选择语法高亮
+pub fn edit(text: &str, extension: &str) -> io::Result<String> {
+    let editor = env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"));
+    let (path, mut file) = create_temp(extension)?;
+    file.write_all(text.as_bytes())?;
+    drop(file);
+    // $EDITOR 可以带参数，例如 `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!("{editor} exited with {status}"))),
+        Err(err) => Err(io::Error::new(err.kind(), format!("{editor}: {err}"))),
+    };
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+/// 在临时目录新建只有自己可读写的文件，文件名带随机部分，已存在时失败而不是覆盖，
+/// 避免别的用户预先放好同名文件或符号链接来读取、篡改正在编辑的内容
+fn create_temp(extension: &str) -> io::Result<(PathBuf, File)> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let random = RandomState::new().hash_one((std::process::id(), nanos));
+    let path = env::temp_dir().join(format!("ratatui-json-editor-{random:016x}.{extension}"));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let file = options.open(&path)?;
+    Ok((path, file))
+}
@@ -0,0 +1,117 @@
+use crate::document::Pairs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 崩溃恢复日志，定期把内存中的文档和撤销位置写入 `$XDG_STATE_HOME`
+/// 只写当前文档，不写撤销历史，日志大小不随修改次数增长
+#[derive(Serialize, Deserialize)]
+pub struct Journal {
+    pub input_path: Option<PathBuf>,
+    pub saved_at: u64, // 写入时间（unix 秒）
+    pub pairs: Pairs,
+    pub history_index: usize, // 写入时的撤销位置，大于 0 表示打开后做过修改
+    #[serde(default)]
+    pub masked: bool, // 机密值是否以占位符代替，恢复时需要从原文件取回
+}
+
+/// 未命名文档的日志文件名前缀，后面是进程号
+const UNTITLED: &str = "untitled-";
+
+/// 日志目录，优先 `$XDG_STATE_HOME`，否则回退到 `~/.local/state`
+fn state_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(base.join("ratatui-json-editor"))
+}
+
+/// 每个输入文件对应一个日志文件，文件名由绝对路径编码而来（与 vim 的 undodir 相同的做法）
+/// 没有输入文件的文档按进程号区分，同时打开的几个未命名会话不会互相覆盖
+pub fn journal_path(input_path: Option<&Path>) -> Option<PathBuf> {
+    let name = match input_path {
+        Some(path) => {
+            let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            path.to_string_lossy().replace('%', "%%").replace('/', "%")
+        }
+        None => format!("{UNTITLED}{}", std::process::id()),
+    };
+    Some(state_dir()?.join(format!("{name}.journal.json")))
+}
+
+/// 把已经退出的进程留下的未命名文档日志改名为本进程的日志，之后与普通日志一样检查和恢复
+/// 有多个时接管最近写入的那个；判断不了进程是否还在运行时不接管
+pub fn adopt_untitled() -> io::Result<()> {
+    let (Some(dir), Some(own)) = (state_dir(), journal_path(None)) else {
+        return Ok(());
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let mut newest: Option<(SystemTime, PathBuf)> = None;
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let pid = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(UNTITLED))
+            .and_then(|name| name.strip_suffix(".journal.json"))
+            .and_then(|pid| pid.parse::<u32>().ok());
+        let Some(pid) = pid else {
+            continue;
+        };
+        if pid == std::process::id() || running(pid) {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+    match newest {
+        Some((_, path)) => fs::rename(path, own),
+        None => Ok(()),
+    }
+}
+
+/// 进程是否还在运行，没有 /proc 的系统上一律当作在运行
+fn running(pid: u32) -> bool {
+    !Path::new("/proc/self").exists() || Path::new("/proc").join(pid.to_string()).exists()
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Journal {
+    /// 先写临时文件再重命名，避免写到一半崩溃留下损坏的日志
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)
+    }
+
+    /// 读取遗留的日志，不存在或无法解析时返回 None
+    pub fn read(path: &Path) -> Option<Journal> {
+        let content = fs::read(path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+}
+
+pub fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,711 @@
+use crate::document::Pairs;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+/// 保留格式的源文件：记录每个节点在原文中的位置，保存时只替换被修改的部分
+pub struct Source {
+    text: String,
+    root: Node,
+    original: Pairs, // 加载时的文档，保存时与当前文档比较
+}
+
+/// 语法树节点
+enum Node {
+    Scalar(Range<usize>),
+    Object(Range<usize>, Vec<Item>),
+    Array(Range<usize>, Vec<Item>),
+}
+
+/// 对象成员或数组元素：key（数组元素没有）及其在原文中的范围、值节点和其后逗号的位置
+struct Item {
+    key: Option<(String, Range<usize>)>,
+    value: Node,
+    comma: Option<usize>,
+}
+
+/// 原文范围和替换文本
+type Edit = (Range<usize>, String);
+
+impl Node {
+    fn span(&self) -> Range<usize> {
+        match self {
+            Node::Scalar(span) | Node::Object(span, _) | Node::Array(span, _) => span.clone(),
+        }
+    }
+}
+
+impl Item {
+    fn key(&self) -> Option<&str> {
+        self.key.as_ref().map(|(key, _)| key.as_str())
+    }
+
+    /// 从 key（数组元素从值）开始到值结束，不含其后的逗号
+    fn range(&self) -> Range<usize> {
+        let span = self.value.span();
+        let start = self
+            .key
+            .as_ref()
+            .map_or(span.start, |(_, range)| range.start);
+        start..span.end
+    }
+}
+
+impl Source {
+    /// 解析 JSONC（允许注释、尾随逗号，以及 JSON5 的无引号 key 和单引号字符串），根节点必须是对象
+    pub fn parse(text: String) -> Result<(Source, Pairs), String> {
+        let mut parser = Parser {
+            text: &text,
+            pos: 0,
+        };
+        let (root, value) = parser.document()?;
+        let Value::Object(pairs) = value else {
+            return Err(String::from("the document root must be an object"));
+        };
+        Ok((
+            Source {
+                text,
+                root,
+                original: pairs.clone(),
+            },
+            pairs,
+        ))
+    }
+
+    /// 把当前文档写回原文：只改动与加载时不同的部分，其余注释、空白和顺序保持不变
+    pub fn render(&self, pairs: &Pairs) -> String {
+        let mut edits = Vec::new();
+        let old = Value::Object(self.original.clone());
+        let new = Value::Object(pairs.clone());
+        patch(&self.text, &self.root, &old, &new, &mut edits);
+        apply(&self.text, 0..self.text.len(), edits)
+    }
+}
+
+/// 把修改应用到原文的 range 这一段上，修改的范围都在这一段之内
+/// 同一位置先做替换再做插入，插入的文本因此不会被替换掉
+fn apply(text: &str, range: Range<usize>, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|(edit, _)| std::cmp::Reverse((edit.start, edit.end)));
+    let mut result = text[range.clone()].to_string();
+    for (edit, replacement) in edits {
+        result.replace_range(
+            edit.start - range.start..edit.end - range.start,
+            &replacement,
+        );
+    }
+    result
+}
+
+/// 比较新旧值，生成需要的修改
+fn patch(text: &str, node: &Node, old: &Value, new: &Value, edits: &mut Vec<Edit>) {
+    if identical(old, new) {
+        return;
+    }
+    match (node, old, new) {
+        // 有重复 key 的对象与 Map 无法一一对应，只能整体替换
+        (Node::Object(span, items), Value::Object(old), Value::Object(new))
+            if items.len() == old.len() =>
+        {
+            let old: Vec<&Value> = items
+                .iter()
+                .map(|item| &old[item.key().unwrap_or_default()])
+                .collect();
+            let sources = match_members(items, &old, new);
+            let new: Vec<(Option<&str>, &Value)> = new
+                .iter()
+                .map(|(key, value)| (Some(key.as_str()), value))
+                .collect();
+            patch_items(text, span, items, &old, &new, &sources, edits);
+        }
+        (Node::Array(span, items), Value::Array(old), Value::Array(new)) => {
+            let sources = match_elements(old, new);
+            let old: Vec<&Value> = old.iter().collect();
+            let new: Vec<(Option<&str>, &Value)> = new.iter().map(|value| (None, value)).collect();
+            patch_items(text, span, items, &old, &new, &sources, edits);
+        }
+        _ => edits.push((node.span(), new.to_string())),
+    }
+}
+
+/// 值相等且对象的 key 顺序也相同（Map 的相等比较不考虑顺序）
+fn identical(old: &Value, new: &Value) -> bool {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            old.len() == new.len()
+                && old.iter().zip(new).all(|((old_key, old), (new_key, new))| {
+                    old_key == new_key && identical(old, new)
+                })
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            old.len() == new.len() && old.iter().zip(new).all(|(old, new)| identical(old, new))
+        }
+        _ => old == new,
+    }
+}
+
+/// 新对象中每个 key 对应的原有成员，None 表示新增
+/// 新 key 紧跟在原来的前一个成员之后、且值与原来该位置上不再存在的成员相同时视为改名
+fn match_members(items: &[Item], old: &[&Value], new: &Pairs) -> Vec<Option<usize>> {
+    let index: HashMap<&str, usize> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((item.key()?, i)))
+        .collect();
+    let mut sources: Vec<Option<usize>> = new
+        .keys()
+        .map(|key| index.get(key.as_str()).copied())
+        .collect();
+    let mut used = vec![false; items.len()];
+    for source in sources.iter().flatten() {
+        used[*source] = true;
+    }
+    for (position, value) in new.values().enumerate() {
+        if sources[position].is_some() {
+            continue;
+        }
+        let candidate = match position {
+            0 => Some(0),
+            _ => sources[position - 1].map(|previous| previous + 1),
+        };
+        if let Some(candidate) = candidate.filter(|candidate| {
+            *candidate < items.len() && !used[*candidate] && identical(old[*candidate], value)
+        }) {
+            sources[position] = Some(candidate);
+            used[candidate] = true;
+        }
+    }
+    sources
+}
+
+/// 新数组中每个元素对应的原有元素，None 表示新增
+/// 首尾相同的部分按位置对应；中间部分先按相同的值对应（移动），剩下的按顺序对应（就地修改）
+fn match_elements(old: &[Value], new: &[Value]) -> Vec<Option<usize>> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| identical(old, new))
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| identical(old, new))
+        .count();
+    let mut sources: Vec<Option<usize>> = vec![None; new.len()];
+    for (index, source) in sources[..prefix].iter_mut().enumerate() {
+        *source = Some(index);
+    }
+    for offset in 1..=suffix {
+        sources[new.len() - offset] = Some(old.len() - offset);
+    }
+
+    let middle = prefix..old.len() - suffix;
+    let mut unused: HashMap<String, VecDeque<usize>> = HashMap::new();
+    for index in middle.clone() {
+        unused
+            .entry(old[index].to_string())
+            .or_default()
+            .push_back(index);
+    }
+    let mut used = vec![false; old.len()];
+    for index in prefix..new.len() - suffix {
+        let found = unused
+            .get_mut(&new[index].to_string())
+            .and_then(VecDeque::pop_front);
+        if let Some(found) = found {
+            sources[index] = Some(found);
+            used[found] = true;
+        }
+    }
+    let mut rest = middle.filter(|index| !used[*index]);
+    for source in &mut sources[prefix..new.len() - suffix] {
+        if source.is_none() {
+            *source = rest.next();
+        }
+    }
+    sources
+}
+
+/// 按对应关系修改容器的成员：保留的成员就地修改，顺序改变时在原来的位置之间移动文本；
+/// 删除的成员连同其逗号删除，新增的成员插在新顺序中前一个保留成员之后
+fn patch_items(
+    text: &str,
+    span: &Range<usize>,
+    items: &[Item],
+    old: &[&Value],
+    new: &[(Option<&str>, &Value)],
+    sources: &[Option<usize>],
+    edits: &mut Vec<Edit>,
+) {
+    // 保留的成员在原文中占据的位置，按原文顺序依次放入新顺序中的成员
+    let mut slots: Vec<usize> = sources.iter().flatten().copied().collect();
+    slots.sort_unstable();
+    let mut kept = vec![false; items.len()];
+    let mut filled = 0;
+    let mut previous = None;
+    let mut added = Vec::new();
+    for (&(key, value), source) in new.iter().zip(sources) {
+        let Some(index) = *source else {
+            added.push(render_item(key, value));
+            continue;
+        };
+        let slot = slots[filled];
+        filled += 1;
+        kept[index] = true;
+        if !added.is_empty() {
+            edits.push(insertion(text, span, items, previous, Some(slot), &added));
+            added.clear();
+        }
+        let item = &items[index];
+        let mut own = Vec::new();
+        // 改名只替换 key 本身
+        if let (Some((old_key, range)), Some(key)) = (&item.key, key) {
+            if old_key != key {
+                own.push((range.clone(), Value::String(key.to_string()).to_string()));
+            }
+        }
+        patch(text, &item.value, old[index], value, &mut own);
+        if slot == index {
+            edits.extend(own);
+        } else {
+            edits.push((items[slot].range(), apply(text, item.range(), own)));
+        }
+        previous = Some(slot);
+    }
+    if !added.is_empty() {
+        edits.push(insertion(text, span, items, previous, None, &added));
+    }
+
+    // 相邻的删除范围可能重叠，合并后再删除
+    let mut removals: Vec<Range<usize>> = Vec::new();
+    for (item, _) in items.iter().zip(&kept).filter(|(_, kept)| !**kept) {
+        let range = removal_range(text, item);
+        match removals.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => removals.push(range),
+        }
+    }
+    // 原文没有尾随逗号时删到了最后一个成员，前面成员逗号后的空白也一起删掉，
+    // 否则 `[1, 2, 3]` 删掉后两个元素会剩下 `[1 ]`
+    let trailing = items.last().is_some_and(|item| item.comma.is_some());
+    if !trailing && kept.last() == Some(&false) {
+        if let Some(last) = removals.last_mut() {
+            last.start = text[..last.start].trim_end().len();
+        }
+    }
+    edits.extend(removals.into_iter().map(|range| (range, String::new())));
+
+    // 原文没有尾随逗号时，最后一个保留成员之后的成员都删除了，它的逗号也要删掉
+    if let Some(comma) = slots.last().and_then(|last| items[*last].comma) {
+        if !trailing {
+            edits.push((comma..comma + 1, String::new()));
+        }
+    }
+}
+
+/// 新增成员的文本
+fn render_item(key: Option<&str>, value: &Value) -> String {
+    match key {
+        Some(key) => format!("{}: {value}", Value::String(key.to_string())),
+        None => value.to_string(),
+    }
+}
+
+/// 删除一个成员：连同其后的逗号删除，成员独占一行时删除整行（包括行尾的 `//` 注释）
+/// 没有逗号的末尾成员删除它前面的空白，上方的注释和其他成员的注释都不动
+fn removal_range(text: &str, item: &Item) -> Range<usize> {
+    let range = item.range();
+    let Some(comma) = item.comma else {
+        let start = text[..range.start].trim_end().len();
+        let rest = &text[range.end..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        let end = if line.trim_start().starts_with("//") {
+            range.end + line.len()
+        } else {
+            range.end
+        };
+        return start..end;
+    };
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let rest = &text[comma + 1..];
+    let line = &rest[..rest.find('\n').map_or(rest.len(), |i| i + 1)];
+    let tail = line.trim();
+    if text[line_start..range.start].trim().is_empty()
+        && (tail.is_empty() || tail.starts_with("//"))
+    {
+        line_start..comma + 1 + line.len()
+    } else {
+        let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+        range.start..comma + 1 + spaces
+    }
+}
+
+/// 成员之间的分隔：独占一行的成员换行并沿用其缩进，否则用一个空格
+fn separator(text: &str, start: usize) -> String {
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &text[line_start..start];
+    if indent.trim().is_empty() {
+        format!("\n{indent}")
+    } else {
+        String::from(" ")
+    }
+}
+
+/// 插入一组新增成员：有前一个保留成员时接在它的值之后（它原来的逗号移到新成员之后），
+/// 否则放在下一个保留成员之前；没有保留的成员时紧接在开括号之后
+fn insertion(
+    text: &str,
+    span: &Range<usize>,
+    items: &[Item],
+    previous: Option<usize>,
+    next: Option<usize>,
+    added: &[String],
+) -> Edit {
+    if let Some(previous) = previous {
+        let separator = separator(text, items[previous].range().start);
+        let at = items[previous].range().end;
+        let insert = added
+            .iter()
+            .map(|item| format!(",{separator}{item}"))
+            .collect();
+        return (at..at, insert);
+    }
+    if let Some(next) = next {
+        let separator = separator(text, items[next].range().start);
+        let at = items[next].range().start;
+        let insert = added
+            .iter()
+            .map(|item| format!("{item},{separator}"))
+            .collect();
+        return (at..at, insert);
+    }
+    let at = span.start + 1;
+    let insert = match items.first() {
+        // 原有成员都被删除：沿用第一个成员的换行和缩进
+        Some(first) => {
+            let separator = separator(text, first.range().start);
+            let lines: Vec<String> = added
+                .iter()
+                .map(|item| format!("{separator}{item}"))
+                .collect();
+            lines.join(",")
+        }
+        None if text.as_bytes()[span.start] == b'{' => format!(" {} ", added.join(", ")),
+        None => added.join(", "),
+    };
+    (at..at, insert)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn document(&mut self) -> Result<(Node, Value), String> {
+        self.skip_trivia()?;
+        let result = self.value()?;
+        self.skip_trivia()?;
+        if self.pos < self.text.len() {
+            return Err(self.error("unexpected trailing characters"));
+        }
+        Ok(result)
+    }
+
+    fn error(&self, message: &str) -> String {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        format!("{message} at line {line} column {column}")
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    /// 跳过空白和 `//`、`/* */` 注释
+    fn skip_trivia(&mut self) -> Result<(), String> {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                let end = trimmed
+                    .find("*/")
+                    .ok_or_else(|| self.error("unterminated comment"))?;
+                self.pos += end + 2;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<(Node, Value), String> {
+        let start = self.pos;
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') | Some(b'\'') => {
+                let text = self.string()?;
+                Ok((Node::Scalar(start..self.pos), Value::String(text)))
+            }
+            Some(_) => {
+                let len = self.text[start..]
+                    .find(|c: char| c.is_whitespace() || matches!(c, ',' | '}' | ']' | '/'))
+                    .unwrap_or(self.text.len() - start);
+                let literal = &self.text[start..start + len];
+                let value: Value = serde_json::from_str(literal)
+                    .map_err(|_| self.error(&format!("invalid value {literal:?}")))?;
+                self.pos += len;
+                Ok((Node::Scalar(start..self.pos), value))
+            }
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<(Node, Value), String> {
+        let start = self.pos;
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        let mut pairs = Pairs::new();
+        loop {
+            self.skip_trivia()?;
+            if self.peek() == Some(b'}') {
+                break;
+            }
+            let key_start = self.pos;
+            let key = self.key()?;
+            let key_range = key_start..self.pos;
+            self.skip_trivia()?;
+            self.expect(b':')?;
+            self.skip_trivia()?;
+            let (node, value) = self.value()?;
+            pairs.insert(key.clone(), value);
+            self.skip_trivia()?;
+            let comma = self.comma(b'}')?;
+            members.push(Item {
+                key: Some((key, key_range)),
+                value: node,
+                comma,
+            });
+            if comma.is_none() {
+                break;
+            }
+        }
+        self.pos += 1;
+        Ok((Node::Object(start..self.pos, members), Value::Object(pairs)))
+    }
+
+    fn array(&mut self) -> Result<(Node, Value), String> {
+        let start = self.pos;
+        self.expect(b'[')?;
+        let mut nodes = Vec::new();
+        let mut values = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.peek() == Some(b']') {
+                break;
+            }
+            let (node, value) = self.value()?;
+            values.push(value);
+            self.skip_trivia()?;
+            let comma = self.comma(b']')?;
+            nodes.push(Item {
+                key: None,
+                value: node,
+                comma,
+            });
+            if comma.is_none() {
+                break;
+            }
+        }
+        self.pos += 1;
+        Ok((Node::Array(start..self.pos, nodes), Value::Array(values)))
+    }
+
+    /// 成员之后的逗号（允许尾随逗号），返回逗号的位置；遇到 close 时返回 None 且不前进
+    fn comma(&mut self, close: u8) -> Result<Option<usize>, String> {
+        match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                Ok(Some(self.pos - 1))
+            }
+            Some(byte) if byte == close => Ok(None),
+            _ => Err(self.error(&format!("expected ',' or '{}'", close as char))),
+        }
+    }
+
+    /// 对象的 key：字符串或 JSON5 风格的标识符
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(b'"') | Some(b'\'') => self.string(),
+            _ => {
+                let rest = &self.text[self.pos..];
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(self.error("expected a key"));
+                }
+                self.pos += len;
+                Ok(rest[..len].to_string())
+            }
+        }
+    }
+
+    /// 读取双引号或单引号字符串，转义规则与 json 相同
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.peek().ok_or_else(|| self.error("expected a string"))?;
+        let start = self.pos;
+        let bytes = self.text.as_bytes();
+        let mut end = start + 1;
+        while end < bytes.len() && bytes[end] != quote {
+            end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
+        if end >= bytes.len() {
+            return Err(self.error("unterminated string"));
+        }
+        let body = &self.text[start + 1..end];
+        // 单引号字符串转换成等价的双引号形式再交给 serde 解码
+        let quoted = if quote == b'\'' {
+            format!("\"{}\"", body.replace("\\'", "'").replace('"', "\\\""))
+        } else {
+            format!("\"{body}\"")
+        };
+        let text = serde_json::from_str(&quoted).map_err(|_| self.error("invalid string"))?;
+        self.pos = end + 1;
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{self, Segment};
+
+    const FIXTURE: &str = r#"{
+  // host comment
+  "host": "localhost",
+  // port comment
+  "port": 8080, // inline
+  "tags": [
+    // first
+    "a",
+    "b" // second
+  ],
+  /* debug comment */
+  "debug": true
+}
+"#;
+
+    /// 修改加载的文档后保存，保存结果重新解析后必须与修改后的文档一致
+    fn round_trip(edit: impl FnOnce(&mut Pairs)) -> String {
+        let (source, mut pairs) = Source::parse(FIXTURE.to_string()).unwrap();
+        edit(&mut pairs);
+        let text = source.render(&pairs);
+        let (_, reparsed) = Source::parse(text.clone()).unwrap();
+        assert!(
+            identical(&Value::Object(reparsed), &Value::Object(pairs)),
+            "{text}"
+        );
+        text
+    }
+
+    fn key(key: &str) -> Segment {
+        Segment::Key(key.to_string())
+    }
+
+    #[test]
+    fn unchanged() {
+        assert_eq!(round_trip(|_| {}), FIXTURE);
+    }
+
+    #[test]
+    fn delete_member_keeps_neighbour_comments() {
+        let text = round_trip(|pairs| document::delete_path(pairs, &[key("host")]).unwrap());
+        assert_eq!(text, FIXTURE.replace("  \"host\": \"localhost\",\n", ""));
+    }
+
+    #[test]
+    fn delete_last_member() {
+        let text = round_trip(|pairs| document::delete_path(pairs, &[key("debug")]).unwrap());
+        assert_eq!(
+            text,
+            FIXTURE
+                .replace("  ],\n", "  ]\n")
+                .replace("\n  \"debug\": true", "")
+        );
+    }
+
+    #[test]
+    fn delete_array_element() {
+        let text = round_trip(|pairs| {
+            document::delete_path(pairs, &[key("tags"), Segment::Index(0)]).unwrap()
+        });
+        assert_eq!(text, FIXTURE.replace("    \"a\",\n", ""));
+    }
+
+    #[test]
+    fn insert_member_and_element() {
+        let text = round_trip(|pairs| {
+            pairs.insert(String::from("name"), Value::from("demo"));
+            let Some(Value::Array(tags)) = pairs.get_mut("tags") else {
+                unreachable!()
+            };
+            tags.insert(1, Value::from("between"));
+        });
+        assert_eq!(
+            text,
+            FIXTURE
+                .replace("\"a\",\n", "\"a\",\n    \"between\",\n")
+                .replace("\"debug\": true", "\"debug\": true,\n  \"name\": \"demo\"")
+        );
+    }
+
+    #[test]
+    fn rename_keeps_position_and_comment() {
+        let text = round_trip(|pairs| {
+            document::rename_path(pairs, &[key("host")], String::from("hostname")).unwrap()
+        });
+        assert_eq!(text, FIXTURE.replace("\"host\":", "\"hostname\":"));
+    }
+
+    #[test]
+    fn move_member_swaps_text() {
+        let text = round_trip(|pairs| document::swap_entries(pairs, 0, 1));
+        assert_eq!(
+            text,
+            FIXTURE
+                .replace("\"host\": \"localhost\"", "@")
+                .replace("\"port\": 8080", "\"host\": \"localhost\"")
+                .replace('@', "\"port\": 8080")
+        );
+    }
+
+    #[test]
+    fn edit_value_in_single_line_object() {
+        let (source, mut pairs) = Source::parse(String::from("{\"a\": 1, \"b\": [1, 2]}")).unwrap();
+        document::delete_path(&mut pairs, &[key("a")]).unwrap();
+        document::set_path(&mut pairs, &[key("b"), Segment::Index(1)], Value::from(3)).unwrap();
+        assert_eq!(source.render(&pairs), "{\"b\": [1, 3]}");
+    }
+
+    #[test]
+    fn delete_trailing_elements_in_single_line_array() {
+        let (source, mut pairs) = Source::parse(String::from("{\"a\": [1, 2, 3]}")).unwrap();
+        document::delete_path(&mut pairs, &[key("a"), Segment::Index(2)]).unwrap();
+        document::delete_path(&mut pairs, &[key("a"), Segment::Index(1)]).unwrap();
+        assert_eq!(source.render(&pairs), "{\"a\": [1]}");
+    }
+}
@@ -0,0 +1,610 @@
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// 超过该大小的文件自动以只读的延迟加载模式打开
+pub const LAZY_THRESHOLD: u64 = 64 * 1024 * 1024;
+/// 每隔多少个成员记录一次偏移量，索引大小因此只有成员数的 1/64
+const CHECKPOINT_INTERVAL: usize = 64;
+/// 列表中每个值最多读取的字节数
+const PREVIEW_BYTES: u64 = 256;
+/// 详情栏中小于该大小的值会被完整解析并格式化
+const DETAIL_PARSE_BYTES: u64 = 64 * 1024;
+
+/// 文件是否大到需要延迟加载
+pub fn should_open_lazily(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|meta| meta.len() > LAZY_THRESHOLD)
+}
+
+/// 文件中一段字节范围 [start, end)
+#[derive(Clone, Copy)]
+pub struct Span {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Span {
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+/// 容器中的一个成员，数组元素没有 key
+#[derive(Clone)]
+pub struct Member {
+    pub key: Option<Span>,
+    pub value: Span,
+}
+
+/// 已建立索引的对象或数组
+pub struct Level {
+    pub label: String,         // 路径中对应的一段，如 `.items` 或 `[3]`
+    pub is_object: bool,
+    checkpoints: Vec<u64>,     // 第 0、64、128…个成员的起始偏移
+    pub len: usize,            // 成员总数
+    pub selected: usize,
+    top: Cell<usize>,          // 可见窗口第一行的下标
+}
+
+/// 列表中一行的内容，只从文件中读取可见的部分
+#[derive(Clone)]
+pub struct Row {
+    pub key: String,
+    pub preview: String,
+}
+
+/// 缓存的可见窗口，层级、起始行或行数变化时重新读取
+struct Window {
+    depth: usize,
+    top: usize,
+    height: usize,
+    rows: Vec<Row>,
+}
+
+/// 正在后台进行的索引任务
+struct Indexing {
+    progress: Arc<AtomicU64>, // 已扫描到的文件偏移
+    span: Span,
+    label: String,
+    receiver: Receiver<io::Result<Level>>,
+}
+
+/// 只读的大文件视图：按需建立偏移索引，只读取可见窗口中的成员，内存占用与文件大小无关
+pub struct LazyDocument {
+    pub path: PathBuf,
+    pub file_len: u64,
+    file: RefCell<BufReader<File>>,
+    pub levels: Vec<Level>,
+    indexing: Option<Indexing>,
+    pub root_scalar: Option<Span>, // 根节点不是容器时直接显示它
+    pub error: Option<String>,
+    window: RefCell<Option<Window>>,
+}
+
+impl LazyDocument {
+    /// 打开文件并在后台开始索引根节点
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::with_capacity(64 * 1024, file);
+        let mut scanner = Scanner::new(&mut reader, 0);
+        scanner.skip_ws()?;
+        let start = scanner.pos;
+        let first = scanner.peek()?;
+        let mut doc = LazyDocument {
+            path: path.to_path_buf(),
+            file_len,
+            file: RefCell::new(reader),
+            levels: Vec::new(),
+            indexing: None,
+            root_scalar: None,
+            error: None,
+            window: RefCell::new(None),
+        };
+        match first {
+            Some(b'{') | Some(b'[') => doc.start_indexing(
+                Span {
+                    start,
+                    end: file_len,
+                },
+                String::from("$"),
+            ),
+            Some(_) => {
+                doc.root_scalar = Some(Span {
+                    start,
+                    end: file_len,
+                })
+            }
+            None => return Err(invalid("empty document")),
+        }
+        Ok(doc)
+    }
+
+    fn start_indexing(&mut self, span: Span, label: String) {
+        let progress = Arc::new(AtomicU64::new(span.start));
+        let (sender, receiver) = mpsc::channel();
+        let path = self.path.clone();
+        let thread_progress = Arc::clone(&progress);
+        let thread_label = label.clone();
+        thread::spawn(move || {
+            let _ = sender.send(index_container(&path, span, thread_label, &thread_progress));
+        });
+        self.indexing = Some(Indexing {
+            progress,
+            span,
+            label,
+            receiver,
+        });
+    }
+
+    /// 索引进度 (0.0..=1.0) 和正在索引的路径，空闲时为 None
+    pub fn progress(&self) -> Option<(f64, &str)> {
+        self.indexing.as_ref().map(|indexing| {
+            let done = indexing.progress.load(Ordering::Relaxed) - indexing.span.start;
+            let total = indexing.span.len().max(1);
+            ((done as f64 / total as f64).min(1.0), indexing.label.as_str())
+        })
+    }
+
+    /// 在事件循环中调用，收取后台索引的结果
+    pub fn poll(&mut self) {
+        let Some(indexing) = &self.indexing else {
+            return;
+        };
+        match indexing.receiver.try_recv() {
+            Ok(Ok(level)) => {
+                self.levels.push(level);
+                self.window.replace(None);
+                self.indexing = None;
+            }
+            Ok(Err(err)) => {
+                self.error = Some(err.to_string());
+                self.indexing = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.indexing = None,
+        }
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.indexing.is_some()
+    }
+
+    pub fn current(&self) -> Option<&Level> {
+        self.levels.last()
+    }
+
+    pub fn select_next(&mut self) {
+        if self.is_indexing() {
+            return;
+        }
+        if let Some(level) = self.levels.last_mut() {
+            if level.selected + 1 < level.len {
+                level.selected += 1;
+            }
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.is_indexing() {
+            return;
+        }
+        if let Some(level) = self.levels.last_mut() {
+            level.selected = level.selected.saturating_sub(1);
+        }
+    }
+
+    /// 进入选中的对象或数组，在后台为它建立索引
+    pub fn enter(&mut self) {
+        if self.is_indexing() {
+            return;
+        }
+        let Some(level) = self.levels.last() else {
+            return;
+        };
+        let Ok(Some(member)) = self.member(level, level.selected) else {
+            return;
+        };
+        if !matches!(self.peek_at(member.value.start), Ok(b'{') | Ok(b'[')) {
+            return;
+        }
+        let label = match member.key {
+            Some(key) => format!(".{}", self.read_key(key).unwrap_or_default()),
+            None => format!("[{}]", level.selected),
+        };
+        self.start_indexing(member.value, label);
+    }
+
+    /// 返回上一层
+    pub fn leave(&mut self) {
+        if self.levels.len() > 1 && !self.is_indexing() {
+            self.levels.pop();
+            self.window.replace(None);
+        }
+    }
+
+    /// 当前所在的各层容器在上一层中的 key，数组元素没有 key
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.levels
+            .iter()
+            .filter_map(|level| level.label.strip_prefix('.'))
+    }
+
+    /// 当前层级的 json 路径
+    pub fn path_of(&self, index: usize) -> String {
+        let mut path: String = self.levels.iter().map(|level| level.label.as_str()).collect();
+        if let Some(level) = self.levels.last() {
+            match self.member(level, index) {
+                Ok(Some(Member { key: Some(key), .. })) => {
+                    path.push('.');
+                    path.push_str(&self.read_key(key).unwrap_or_default());
+                }
+                _ => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+
+    /// 可见窗口中的行，根据选中行调整窗口位置，只在窗口变化时读取文件
+    pub fn visible_rows(&self, height: usize) -> io::Result<(usize, Vec<Row>)> {
+        let depth = self.levels.len();
+        let Some(level) = self.levels.last() else {
+            return Ok((0, Vec::new()));
+        };
+        let mut top = level.top.get();
+        if level.selected < top {
+            top = level.selected;
+        } else if height > 0 && level.selected >= top + height {
+            top = level.selected + 1 - height;
+        }
+        level.top.set(top);
+
+        if let Some(window) = &*self.window.borrow() {
+            if (window.depth, window.top, window.height) == (depth, top, height) {
+                return Ok((top, window.rows.clone()));
+            }
+        }
+        let mut rows = Vec::new();
+        for (offset, member) in self.members(level, top, height)?.into_iter().enumerate() {
+            rows.push(self.row(&member, top + offset)?);
+        }
+        self.window.replace(Some(Window {
+            depth,
+            top,
+            height,
+            rows: rows.clone(),
+        }));
+        Ok((top, rows))
+    }
+
+    /// 选中成员的详情：类型、字节数和内容（小值完整格式化，大值只显示开头）
+    pub fn detail(&self) -> io::Result<Option<(&'static str, u64, String)>> {
+        let span = match (self.levels.last(), self.root_scalar) {
+            (Some(level), _) => match self.member(level, level.selected)? {
+                Some(member) => member.value,
+                None => return Ok(None),
+            },
+            (None, Some(span)) => span,
+            (None, None) => return Ok(None),
+        };
+        let kind = kind_of(self.peek_at(span.start)?);
+        let text = if span.len() <= DETAIL_PARSE_BYTES {
+            let bytes = self.read_span(span, span.len())?;
+            match serde_json::from_slice::<serde_json::Value>(&bytes) {
+                Ok(value) => crate::document::preview_value(&value),
+                Err(_) => String::from_utf8_lossy(&bytes).trim_end().to_string(),
+            }
+        } else {
+            let bytes = self.read_span(span, DETAIL_PARSE_BYTES)?;
+            format!("{}…", String::from_utf8_lossy(&bytes))
+        };
+        Ok(Some((kind, span.len(), text)))
+    }
+
+    fn row(&self, member: &Member, index: usize) -> io::Result<Row> {
+        let key = match member.key {
+            Some(key) => self.read_key(key)?,
+            None => index.to_string(),
+        };
+        let bytes = self.read_span(member.value, PREVIEW_BYTES)?;
+        let mut preview: String = String::from_utf8_lossy(&bytes)
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        if member.value.len() > PREVIEW_BYTES {
+            preview.push('…');
+        }
+        Ok(Row { key, preview })
+    }
+
+    /// 从最近的检查点开始扫描，读取第 first 个成员起的 count 个成员
+    fn members(&self, level: &Level, first: usize, count: usize) -> io::Result<Vec<Member>> {
+        if first >= level.len {
+            return Ok(Vec::new());
+        }
+        let checkpoint = first / CHECKPOINT_INTERVAL;
+        let mut reader = self.file.borrow_mut();
+        let mut scanner = Scanner::new(&mut *reader, level.checkpoints[checkpoint]);
+        let mut members = Vec::new();
+        let mut index = checkpoint * CHECKPOINT_INTERVAL;
+        let mut at_start = true;
+        while members.len() < count && index < level.len {
+            if !at_start {
+                scanner.expect_separator()?;
+            }
+            at_start = false;
+            let member = scanner.member(level.is_object)?;
+            if index >= first {
+                members.push(member);
+            }
+            index += 1;
+        }
+        Ok(members)
+    }
+
+    fn member(&self, level: &Level, index: usize) -> io::Result<Option<Member>> {
+        Ok(self.members(level, index, 1)?.into_iter().next())
+    }
+
+    fn read_span(&self, span: Span, limit: u64) -> io::Result<Vec<u8>> {
+        let mut reader = self.file.borrow_mut();
+        reader.seek(SeekFrom::Start(span.start))?;
+        let mut bytes = Vec::new();
+        (&mut *reader).take(span.len().min(limit)).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_key(&self, span: Span) -> io::Result<String> {
+        let bytes = self.read_span(span, span.len())?;
+        serde_json::from_slice(&bytes).map_err(io::Error::from)
+    }
+
+    fn peek_at(&self, pos: u64) -> io::Result<u8> {
+        let bytes = self.read_span(Span { start: pos, end: pos + 1 }, 1)?;
+        bytes.first().copied().ok_or_else(|| invalid("unexpected end of file"))
+    }
+}
+
+/// 扫描一个容器的全部成员，每隔 CHECKPOINT_INTERVAL 个成员记录起始偏移
+fn index_container(path: &Path, span: Span, label: String, progress: &AtomicU64) -> io::Result<Level> {
+    let mut reader = BufReader::with_capacity(256 * 1024, File::open(path)?);
+    let mut scanner = Scanner::new(&mut reader, span.start);
+    scanner.progress = Some(progress);
+    let is_object = match scanner.next()? {
+        b'{' => true,
+        b'[' => false,
+        _ => return Err(invalid("expected an object or array")),
+    };
+    let close = if is_object { b'}' } else { b']' };
+    let mut checkpoints = Vec::new();
+    let mut len = 0;
+    scanner.skip_ws()?;
+    if scanner.peek()? == Some(close) {
+        scanner.next()?;
+    } else {
+        loop {
+            scanner.skip_ws()?;
+            if len % CHECKPOINT_INTERVAL == 0 {
+                checkpoints.push(scanner.pos);
+            }
+            scanner.member(is_object)?;
+            scanner.report_progress();
+            len += 1;
+            scanner.skip_ws()?;
+            match scanner.next()? {
+                b',' => {}
+                c if c == close => break,
+                _ => return Err(invalid("expected ',' or closing bracket")),
+            }
+        }
+    }
+    Ok(Level {
+        label,
+        is_object,
+        checkpoints,
+        len,
+        selected: 0,
+        top: Cell::new(0),
+    })
+}
+
+fn kind_of(first: u8) -> &'static str {
+    match first {
+        b'{' => "object",
+        b'[' => "array",
+        b'"' => "string",
+        b't' | b'f' => "boolean",
+        b'n' => "null",
+        _ => "number",
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 只识别结构、不构建值的逐字节扫描器
+struct Scanner<'a, R: BufRead + Seek> {
+    reader: &'a mut R,
+    pos: u64,
+    seeked: bool,
+    progress: Option<&'a AtomicU64>, // 后台索引时报告扫描位置
+}
+
+impl<'a, R: BufRead + Seek> Scanner<'a, R> {
+    fn new(reader: &'a mut R, pos: u64) -> Self {
+        Scanner {
+            reader,
+            pos,
+            seeked: false,
+            progress: None,
+        }
+    }
+
+    fn report_progress(&self) {
+        if let Some(progress) = self.progress {
+            progress.store(self.pos, Ordering::Relaxed);
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        if !self.seeked {
+            self.reader.seek(SeekFrom::Start(self.pos))?;
+            self.seeked = true;
+        }
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn next(&mut self) -> io::Result<u8> {
+        let byte = self.peek()?.ok_or_else(|| invalid("unexpected end of file"))?;
+        self.reader.consume(1);
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn skip_ws(&mut self) -> io::Result<()> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    /// 读取一个成员（对象成员包括 key 和冒号），返回 key 和值的范围
+    fn member(&mut self, is_object: bool) -> io::Result<Member> {
+        self.skip_ws()?;
+        let key = if is_object {
+            let start = self.pos;
+            if self.next()? != b'"' {
+                return Err(invalid("expected a string key"));
+            }
+            self.skip_string_body()?;
+            let key = Span {
+                start,
+                end: self.pos,
+            };
+            self.skip_ws()?;
+            if self.next()? != b':' {
+                return Err(invalid("expected ':'"));
+            }
+            self.skip_ws()?;
+            Some(key)
+        } else {
+            None
+        };
+        let start = self.pos;
+        self.skip_value()?;
+        Ok(Member {
+            key,
+            value: Span {
+                start,
+                end: self.pos,
+            },
+        })
+    }
+
+    fn expect_separator(&mut self) -> io::Result<()> {
+        self.skip_ws()?;
+        match self.next()? {
+            b',' => Ok(()),
+            _ => Err(invalid("expected ','")),
+        }
+    }
+
+    /// 跳过开头引号之后的字符串内容
+    fn skip_string_body(&mut self) -> io::Result<()> {
+        loop {
+            if self.peek()?.is_none() {
+                return Err(invalid("unterminated string"));
+            }
+            // 批量跳过普通字符，遇到引号或反斜杠再逐个处理
+            let (run, available) = {
+                let buf = self.reader.fill_buf()?;
+                let run = buf.iter().position(|b| *b == b'"' || *b == b'\\').unwrap_or(buf.len());
+                (run, buf.len())
+            };
+            self.reader.consume(run);
+            self.pos += run as u64;
+            if run == available {
+                continue;
+            }
+            match self.next()? {
+                b'"' => return Ok(()),
+                _ => {
+                    self.next()?;
+                }
+            }
+        }
+    }
+
+    /// 跳过任意 json 值，容器只计算嵌套深度
+    fn skip_value(&mut self) -> io::Result<()> {
+        match self.peek()?.ok_or_else(|| invalid("unexpected end of file"))? {
+            b'"' => {
+                self.next()?;
+                self.skip_string_body()
+            }
+            b'{' | b'[' => {
+                // 按缓冲区整块扫描，只跟踪字符串和嵌套深度
+                let mut depth = 0usize;
+                let mut in_string = false;
+                let mut escaped = false;
+                loop {
+                    if self.peek()?.is_none() {
+                        return Err(invalid("unexpected end of file"));
+                    }
+                    let (consumed, done) = {
+                        let buf = self.reader.fill_buf()?;
+                        let mut done = false;
+                        let mut consumed = 0;
+                        for &byte in buf {
+                            consumed += 1;
+                            if in_string {
+                                if escaped {
+                                    escaped = false;
+                                } else if byte == b'\\' {
+                                    escaped = true;
+                                } else if byte == b'"' {
+                                    in_string = false;
+                                }
+                                continue;
+                            }
+                            match byte {
+                                b'"' => in_string = true,
+                                b'{' | b'[' => depth += 1,
+                                b'}' | b']' => {
+                                    depth -= 1;
+                                    if depth == 0 {
+                                        done = true;
+                                        break;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        (consumed, done)
+                    };
+                    self.reader.consume(consumed);
+                    self.pos += consumed as u64;
+                    self.report_progress();
+                    if done {
+                        return Ok(());
+                    }
+                }
+            }
+            _ => {
+                while let Some(byte) = self.peek()? {
+                    if matches!(byte, b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r') {
+                        break;
+                    }
+                    self.next()?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
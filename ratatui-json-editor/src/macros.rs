@@ -0,0 +1,180 @@
+use crate::config;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 录制的按键宏，按寄存器（a-z）保存，跨标签页共享
+#[derive(Default)]
+pub struct Macros {
+    registers: BTreeMap<char, Vec<KeyEvent>>,
+    pub recording: Option<(char, Vec<KeyEvent>)>, // 正在录制的寄存器和已录下的按键
+    pub pending: bool,                            // 按下 `@` 之后等待寄存器名
+    pub count: usize,                             // `@` 之前输入的重复次数，0 表示一次
+}
+
+/// 宏文件位于配置目录
+fn macros_path() -> Option<PathBuf> {
+    Some(config::config_dir()?.join("macros.json"))
+}
+
+impl Macros {
+    /// 读取保存的宏，文件不存在时为空
+    pub fn load() -> Result<Macros, String> {
+        let mut macros = Macros::default();
+        let Some(path) = macros_path() else {
+            return Ok(macros);
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(macros),
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        };
+        let saved: BTreeMap<char, String> =
+            serde_json::from_str(&content).map_err(|err| format!("{}: {err}", path.display()))?;
+        for (register, keys) in saved {
+            macros.registers.insert(register, decode(&keys)?);
+        }
+        Ok(macros)
+    }
+
+    /// 以可读的按键记法写入配置目录，便于手工编辑
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = macros_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let saved: BTreeMap<char, String> = self
+            .registers
+            .iter()
+            .map(|(register, keys)| (*register, encode(keys)))
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&saved)?)
+    }
+
+    pub fn get(&self, register: char) -> Option<&Vec<KeyEvent>> {
+        self.registers.get(&register)
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    pub fn record(&mut self, key: KeyEvent) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key);
+        }
+    }
+
+    /// 结束录制并保存，返回录制的寄存器
+    pub fn stop_recording(&mut self) -> io::Result<Option<char>> {
+        let Some((register, keys)) = self.recording.take() else {
+            return Ok(None);
+        };
+        self.registers.insert(register, keys);
+        self.save()?;
+        Ok(Some(register))
+    }
+}
+
+/// 按键记法：普通字符原样，特殊键用 `<CR>`、`<Esc>`、`<C-s>` 等，`<` 本身写作 `<lt>`
+pub fn encode(keys: &[KeyEvent]) -> String {
+    let mut text = String::new();
+    for key in keys {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let name = match key.code {
+            KeyCode::Char('<') if !control => String::from("lt"),
+            KeyCode::Char(c) if !control => {
+                text.push(c);
+                continue;
+            }
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => String::from("CR"),
+            KeyCode::Esc => String::from("Esc"),
+            KeyCode::Backspace => String::from("BS"),
+            KeyCode::Tab => String::from("Tab"),
+            KeyCode::BackTab => String::from("S-Tab"),
+            KeyCode::Up => String::from("Up"),
+            KeyCode::Down => String::from("Down"),
+            KeyCode::Left => String::from("Left"),
+            KeyCode::Right => String::from("Right"),
+            _ => continue,
+        };
+        let prefix = if control { "C-" } else { "" };
+        text.push_str(&format!("<{prefix}{name}>"));
+    }
+    text
+}
+
+pub fn decode(text: &str) -> Result<Vec<KeyEvent>, String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c != '<' {
+            keys.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let end = rest
+            .find('>')
+            .ok_or_else(|| format!("unterminated < in macro {text:?}"))?;
+        let name = &rest[1..end];
+        rest = &rest[end + 1..];
+        let (modifiers, name) = match name.strip_prefix("C-") {
+            Some(name) => (KeyModifiers::CONTROL, name),
+            None => (KeyModifiers::NONE, name),
+        };
+        let code = match name {
+            "lt" => KeyCode::Char('<'),
+            "CR" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "BS" => KeyCode::Backspace,
+            "Tab" => KeyCode::Tab,
+            "S-Tab" => KeyCode::BackTab,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !modifiers.is_empty() => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key <{name}> in macro {text:?}")),
+                }
+            }
+        };
+        keys.push(KeyEvent::new(code, modifiers));
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_round_trip() {
+        let text = "ix<lt>y<CR><C-s><Esc><BS><Tab><S-Tab><Up><Down><Left><Right><C-e>é";
+        let keys = decode(text).unwrap();
+        assert_eq!(keys.len(), 16);
+        assert_eq!(
+            keys[2],
+            KeyEvent::new(KeyCode::Char('<'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            keys[5],
+            KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(encode(&keys), text);
+    }
+
+    #[test]
+    fn decode_rejects_unknown_keys() {
+        assert!(decode("a<CR").is_err());
+        assert!(decode("<Home>").is_err());
+        assert!(decode("<x>").is_err());
+    }
+}
@@ -1,128 +1,946 @@
 use app::{App, CurrentScreen, CurrentlyEditing};
-use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode};
+use cli::Args;
+use config::Config;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::cursor::Show;
 use crossterm::execute;
 use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+    LeaveAlternateScreen,
 };
 use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::Terminal;
 use std::error::Error;
 use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use macros::Macros;
+use replace::Focus;
+use schema::Schema;
+use tabs::Tabs;
 use ui::ui;
 
 mod app;
+mod browser;
+mod cli;
+mod command;
+mod completion;
+mod config;
+mod document;
+mod editor;
+mod journal;
+mod jsonc;
+mod lazy;
+mod macros;
+mod ndjson;
+mod replace;
+mod schema;
+mod secrets;
+mod stats;
+mod tabs;
+mod templates;
+mod watch;
 mod ui;
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<bool> {
-    loop {
-        terminal.draw(|f| ui(f, app))?;
+/// 自动写入恢复日志的间隔
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+/// 后台索引时刷新进度条的间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// 检查文件监听通知的间隔
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 延迟加载模式下的按键处理，返回 true 表示退出
+fn handle_lazy_key(lazy: &mut lazy::LazyDocument, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') => return true,
+        KeyCode::Down | KeyCode::Char('j') => lazy.select_next(),
+        KeyCode::Up | KeyCode::Char('k') => lazy.select_previous(),
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => lazy.enter(),
+        KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => lazy.leave(),
+        _ => {}
+    }
+    false
+}
+
+/// 进入终端界面：原始模式、备用屏幕和鼠标，终端支持时开启键盘增强协议
+fn enter_terminal(keyboard_enhancement: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    if keyboard_enhancement {
+        execute!(
+            stderr,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+    }
+    Ok(())
+}
+
+/// 恢复终端，退出时和临时交给外部编辑器时都经过这里
+fn leave_terminal(keyboard_enhancement: bool) -> io::Result<()> {
+    let mut stderr = io::stderr();
+    if keyboard_enhancement {
+        execute!(stderr, PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    execute!(stderr, LeaveAlternateScreen, DisableMouseCapture, Show)
+}
+
+/// Ctrl-E：暂停终端界面，在 $EDITOR 中编辑弹窗的输入或选中的值，回来后校验并应用
+fn open_in_editor<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    keyboard_enhancement: bool,
+) -> io::Result<()> {
+    app.open_in_editor = false;
+    let Some((text, extension)) = app.editor_text() else {
+        app.status = Some(String::from("Nothing to edit"));
+        return Ok(());
+    };
+    leave_terminal(keyboard_enhancement)?;
+    let edited = editor::edit(&text, extension);
+    enter_terminal(keyboard_enhancement)?;
+    terminal.clear()?;
+    match edited {
+        Ok(edited) => app.apply_editor_text(edited),
+        Err(err) => app.status = Some(format!("Editor failed: {err}")),
+    }
+    Ok(())
+}
+
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    tabs: &mut Tabs,
+    keyboard_enhancement: bool,
+) -> io::Result<()> {
+    let mut last_autosave = Instant::now();
+    while !tabs.is_empty() {
+        // 先收取后台索引结果再绘制，避免完成后一直显示进度条
+        for app in &mut tabs.apps {
+            if let Some(lazy) = &mut app.lazy {
+                lazy.poll();
+            }
+            app.check_disk();
+        }
+        // 按键或宏回放请求了外部编辑器
+        if tabs.current().open_in_editor {
+            open_in_editor(terminal, tabs.current_mut(), keyboard_enhancement)?;
+        }
+        terminal.draw(|f| ui(f, tabs))?;
+        if last_autosave.elapsed() >= AUTOSAVE_INTERVAL {
+            // 日志写不进去（磁盘满、目录不可写）不应让编辑器退出，提示后继续编辑
+            for app in &mut tabs.apps {
+                if let Err(err) = app.autosave() {
+                    app.status = Some(format!("Recovery journal not written: {err}"));
+                }
+            }
+            last_autosave = Instant::now();
+        }
+        // 等待按键时也要定期醒来写日志和检查文件改动，后台索引时更频繁地刷新进度
+        let mut timeout = AUTOSAVE_INTERVAL
+            .saturating_sub(last_autosave.elapsed())
+            .min(WATCH_INTERVAL);
+        let indexing = tabs
+            .apps
+            .iter()
+            .any(|app| app.lazy.as_ref().is_some_and(|lazy| lazy.is_indexing()));
+        if indexing {
+            timeout = timeout.min(PROGRESS_INTERVAL);
+        }
+        if !event::poll(timeout)? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
             if key.kind == event::KeyEventKind::Release {
                 // 跳过非按下键盘事件
                 continue;
             }
-            match app.current_screen {
-                CurrentScreen::Main => match key.code {
-                    KeyCode::Char('e') => {
-                        // 主屏幕按下e进入编辑
-                        app.current_screen = CurrentScreen::Editing;
-                        app.currently_editing = Some(app::CurrentlyEditing::Key);
+            if handle_macro_key(tabs, key)? {
+                continue;
+            }
+            tabs.macros.record(key);
+            dispatch(tabs, key)?;
+        }
+    }
+    Ok(())
+}
+
+/// 把一个按键交给当前标签页，录制的宏回放时也经过这里
+fn dispatch(tabs: &mut Tabs, key: KeyEvent) -> io::Result<()> {
+    tabs.current_mut().status = None;
+    // 主屏幕上的标签页切换和跨标签页复制粘贴；延迟加载和 JSON Lines 记录列表中没有可复制粘贴的文档
+    let app = tabs.current();
+    let structured = app.lazy.is_none() && !app.in_record_list();
+    if let CurrentScreen::Main = tabs.current().current_screen {
+        match key.code {
+            KeyCode::Tab => {
+                tabs.next();
+                return Ok(());
+            }
+            KeyCode::BackTab => {
+                tabs.previous();
+                return Ok(());
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                tabs.current_mut().start_browsing();
+                return Ok(());
+            }
+            KeyCode::Char('y') if structured => {
+                tabs.copy_selected();
+                return Ok(());
+            }
+            KeyCode::Char('p') if structured => {
+                tabs.paste();
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+    if let Some(do_print) = handle_key(tabs.current_mut(), key)? {
+        tabs.close_current(do_print);
+        return Ok(());
+    }
+    // 文件浏览器中选中的文件在新标签页打开，沿用当前标签页的配置和 schema
+    if let Some(path) = tabs.current_mut().opening.take() {
+        match open_document(path, false, false) {
+            Ok(mut app) => {
+                app.config = tabs.current().config.clone();
+                app.schema = tabs.current().schema.clone();
+                app.watch();
+                app.check_recovery();
+                tabs.open(app);
+            }
+            Err(err) => tabs.current_mut().status = Some(format!("Cannot open: {err}")),
+        }
+    }
+    Ok(())
+}
+
+/// 按文件类型打开文档：JSON Lines 逐行打开，过大的文件以只读延迟加载模式打开
+fn open_document(path: PathBuf, ndjson: bool, lazy: bool) -> io::Result<App> {
+    if ndjson || ndjson::is_ndjson_path(&path) {
+        App::open_ndjson(path)
+    } else if lazy || lazy::should_open_lazily(&path) {
+        App::open_lazy(path)
+    } else {
+        App::open(path)
+    }
+}
+
+/// 宏的录制和回放：`qa` 开始录制到寄存器 a（`q` 先进入退出提示，y/n 以外的字母开始录制），
+/// 录制中再按 `q` 结束；`3@a` 回放三次。返回 true 表示按键已被处理
+fn handle_macro_key(tabs: &mut Tabs, key: KeyEvent) -> io::Result<bool> {
+    let app = tabs.current();
+    if app.lazy.is_some() {
+        return Ok(false);
+    }
+    match (&app.current_screen, key.code) {
+        (CurrentScreen::Exiting, KeyCode::Char(register))
+            if register.is_ascii_lowercase() && !matches!(register, 'y' | 'n') =>
+        {
+            tabs.macros.start_recording(register);
+            let app = tabs.current_mut();
+            app.current_screen = CurrentScreen::Main;
+            app.status = Some(format!("Recording @{register}"));
+        }
+        (CurrentScreen::Main, code) => {
+            if std::mem::take(&mut tabs.macros.pending) {
+                let times = std::mem::take(&mut tabs.macros.count).max(1);
+                if let KeyCode::Char(register) = code {
+                    if let Err(err) = replay(tabs, register, times) {
+                        if !tabs.is_empty() {
+                            tabs.current_mut().status = Some(err);
+                        }
                     }
-                    KeyCode::Char('q') => {
-                        // 主屏幕按下q进入推出提示
-                        app.current_screen = CurrentScreen::Exiting;
+                }
+                return Ok(true);
+            }
+            match code {
+                KeyCode::Char(digit @ '0'..='9') if digit != '0' || tabs.macros.count > 0 => {
+                    let digit = digit.to_digit(10).unwrap_or(0) as usize;
+                    tabs.macros.count = tabs.macros.count.saturating_mul(10).saturating_add(digit);
+                }
+                KeyCode::Char('q') if tabs.macros.recording.is_some() => {
+                    if let Some(register) = tabs.macros.stop_recording()? {
+                        tabs.current_mut().status = Some(format!("Recorded @{register}"));
+                    }
+                }
+                KeyCode::Char('@') => {
+                    tabs.macros.pending = true;
+                }
+                _ => {
+                    tabs.macros.count = 0;
+                    return Ok(false);
+                }
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// 把寄存器中的按键依次交给当前标签页，重复 times 次；标签页全部关闭时提前结束
+fn replay(tabs: &mut Tabs, register: char, times: usize) -> Result<(), String> {
+    let keys = tabs
+        .macros
+        .get(register)
+        .cloned()
+        .ok_or_else(|| format!("Macro @{register} is empty"))?;
+    for _ in 0..times {
+        for key in &keys {
+            if tabs.is_empty() {
+                return Ok(());
+            }
+            dispatch(tabs, *key).map_err(|err| err.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// 当前标签页的按键处理，返回 Some(do_print) 表示关闭该标签页
+fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<Option<bool>> {
+    if let Some(lazy) = &mut app.lazy {
+        // 延迟加载模式只读，没有编辑和退出确认
+        if handle_lazy_key(lazy, key) {
+            return Ok(Some(false));
+        }
+        return Ok(None);
+    }
+    match app.current_screen {
+        // JSON Lines 的记录列表，选中一行后在结构化编辑器中打开
+        CurrentScreen::Main if app.in_record_list() => match key.code {
+            KeyCode::Char('q') => {
+                app.current_screen = CurrentScreen::Exiting;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(records) = &mut app.records {
+                    records.select_next();
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(records) = &mut app.records {
+                    records.select_previous();
+                }
+            }
+            KeyCode::Enter => {
+                app.open_record();
+            }
+            _ => {}
+        },
+        CurrentScreen::Main => match key.code {
+            // 在外部编辑器中编辑选中的值
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.open_in_editor = true;
+            }
+            KeyCode::Char('I') => {
+                app.show_stats();
+            }
+            KeyCode::Char('R') => {
+                app.start_replace();
+            }
+            // 数组中 a 在末尾追加元素，只需要输入值
+            KeyCode::Char('a') if app.is_array() => {
+                app.start_insert(app.entries().len());
+            }
+            KeyCode::Char('i') => {
+                app.start_insert(app.selected().unwrap_or(0));
+            }
+            KeyCode::Char('o') => {
+                app.start_insert(app.selected().map_or(0, |index| index + 1));
+            }
+            KeyCode::Char('e') => {
+                // 主屏幕按下e新建成员，有模板时先选择模板
+                app.start_new_entry();
+            }
+            KeyCode::Char('q') => {
+                // 主屏幕按下q进入推出提示
+                app.current_screen = CurrentScreen::Exiting;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.select_next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.select_previous();
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                app.enter();
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                app.leave();
+            }
+            // 先退出嵌套的容器，已在顶层时回到记录列表
+            KeyCode::Backspace if !app.path.is_empty() => {
+                app.leave();
+            }
+            KeyCode::Backspace if app.records.is_some() => {
+                app.close_record();
+            }
+            KeyCode::Char('u') => {
+                app.undo();
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.redo();
+            }
+            KeyCode::Char('r') => {
+                app.start_rename();
+            }
+            KeyCode::Char('J') => {
+                app.move_selected(true);
+            }
+            KeyCode::Char('K') => {
+                app.move_selected(false);
+            }
+            KeyCode::Char('x') => {
+                app.delete_selected();
+            }
+            KeyCode::Char('D') => {
+                app.duplicate_selected();
+            }
+            KeyCode::Char('s') => {
+                app.sort_current();
+            }
+            KeyCode::Char('S') => {
+                app.start_sort_by_field();
+            }
+            KeyCode::Char(':') => {
+                app.current_screen = CurrentScreen::Command;
+            }
+            KeyCode::Char('m') => {
+                app.toggle_secrets();
+            }
+            _ => {}
+        },
+        // 底部命令行，Tab 补全命令名和 json 路径
+        CurrentScreen::Command => {
+            if key.code != KeyCode::Tab {
+                app.completions.clear();
+            }
+            match key.code {
+                KeyCode::Enter => {
+                    let line = std::mem::take(&mut app.command_input);
+                    app.current_screen = CurrentScreen::Main;
+                    return Ok(command::execute(app, &line));
+                }
+                KeyCode::Esc => {
+                    app.command_input.clear();
+                    app.current_screen = CurrentScreen::Main;
+                }
+                // 删空后再按退格退出命令行
+                KeyCode::Backspace if app.command_input.is_empty() => {
+                    app.current_screen = CurrentScreen::Main;
+                }
+                KeyCode::Backspace => {
+                    app.command_input.pop();
+                }
+                KeyCode::Tab => {
+                    let (line, completions) = command::complete(app, &app.command_input);
+                    app.command_input = line;
+                    app.completions = completions;
+                }
+                KeyCode::Char(value) => {
+                    app.command_input.push(value);
+                }
+                _ => {}
+            }
+        }
+        CurrentScreen::Templates => match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(picker) = &mut app.picker {
+                    picker.selected = (picker.selected + 1) % picker.len();
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(picker) = &mut app.picker {
+                    picker.selected = (picker.selected + picker.len() - 1) % picker.len();
+                }
+            }
+            KeyCode::Enter => {
+                app.choose_template();
+            }
+            KeyCode::Esc => {
+                app.picker = None;
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Open => match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(browser) = &mut app.browser {
+                    browser.select_next();
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(browser) = &mut app.browser {
+                    browser.select_previous();
+                }
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                app.choose_file();
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace => {
+                if let Some(browser) = &mut app.browser {
+                    if let Err(err) = browser.leave() {
+                        app.status = Some(err.to_string());
+                    }
+                }
+            }
+            KeyCode::Esc => {
+                app.browser = None;
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Recovering => match key.code {
+            KeyCode::Char('y') => {
+                app.restore_journal();
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Char('n') => {
+                app.discard_journal()?;
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Stats => match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('I') => {
+                app.stats = None;
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Replace => {
+            let Some(replace) = &mut app.replace else {
+                app.current_screen = CurrentScreen::Main;
+                return Ok(None);
+            };
+            let control = key.modifiers.contains(KeyModifiers::CONTROL);
+            if replace.focus == Focus::Matches {
+                match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        replace.selected = (replace.selected + 1) % replace.matches.len();
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        replace.selected = (replace.selected + replace.matches.len() - 1) % replace.matches.len();
+                    }
+                    KeyCode::Char(' ') => {
+                        if let Some(found) = replace.matches.get_mut(replace.selected) {
+                            found.accepted = !found.accepted;
+                        }
+                    }
+                    KeyCode::Char('a') | KeyCode::Char('n') => {
+                        let accepted = key.code == KeyCode::Char('a');
+                        for found in &mut replace.matches {
+                            found.accepted = accepted;
+                        }
                     }
-                    _ => {}
-                },
-                CurrentScreen::Exiting => match key.code {
-                    KeyCode::Char('y') => {
-                        return Ok(true);
-                    },
-                    KeyCode::Char('n') => {
-                        return Ok(false);
-                    },
-                    _ => {}
-                },
-                CurrentScreen::Editing => match key.code {
                     KeyCode::Enter => {
-                        // 用户在编辑界面按下Enter要判断,
-                        // 1. 如果是编辑key则进入编辑value界面
-                        // 2. 如果是编辑value则保存值
-                        if let Some(editing) = &app.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Key => {
-                                    app.currently_editing = Some(CurrentlyEditing::Value);
-                                },
-                                CurrentlyEditing::Value => {
-                                    app.save_key_value();
-                                    app.current_screen = CurrentScreen::Main;
-                                }
-                            }
+                        app.apply_replace();
+                    }
+                    KeyCode::Tab | KeyCode::Esc => {
+                        replace.focus = Focus::Find;
+                    }
+                    _ => {}
+                }
+                return Ok(None);
+            }
+            // 查找和替换框：条件变化后立即重新列出匹配项
+            match key.code {
+                KeyCode::Char('r') if control => {
+                    replace.regex = !replace.regex;
+                    app.search_replace();
+                }
+                KeyCode::Char('k') if control => {
+                    replace.scope = replace.scope.next();
+                    app.search_replace();
+                }
+                KeyCode::Char('l') if control => {
+                    replace.whole_document = !replace.whole_document;
+                    app.search_replace();
+                }
+                KeyCode::Tab => {
+                    replace.focus = if replace.focus == Focus::Find { Focus::Replacement } else { Focus::Find };
+                }
+                KeyCode::Enter | KeyCode::Down if !replace.matches.is_empty() => {
+                    replace.focus = Focus::Matches;
+                }
+                KeyCode::Backspace => {
+                    replace.input_mut().pop();
+                    app.search_replace();
+                }
+                KeyCode::Char(value) if !control => {
+                    replace.input_mut().push(value);
+                    app.search_replace();
+                }
+                KeyCode::Esc => {
+                    app.replace = None;
+                    app.current_screen = CurrentScreen::Main;
+                }
+                _ => {}
+            }
+        }
+        CurrentScreen::Changed => match key.code {
+            KeyCode::Char('r') => {
+                if let Err(err) = app.reload_external() {
+                    app.status = Some(err);
+                }
+            }
+            KeyCode::Char('m') => {
+                if let Err(err) = app.merge_external() {
+                    app.status = Some(err);
+                }
+            }
+            KeyCode::Char('k') => {
+                if let Err(err) = app.keep_mine() {
+                    app.status = Some(err);
+                }
+            }
+            // 稍后再决定，横幅会一直显示
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Exiting => match key.code {
+            // 输出前文件已被外部改写时先选择如何处理
+            KeyCode::Char('y') if app.external.is_some() => {
+                app.current_screen = CurrentScreen::Changed;
+            }
+            KeyCode::Char('y') => {
+                return Ok(Some(true));
+            },
+            KeyCode::Char('n') => {
+                return Ok(Some(false));
+            },
+            _ => {}
+        },
+        // 在外部编辑器中编辑正在输入的框
+        CurrentScreen::Editing if key.code == KeyCode::Char('e') && key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.open_in_editor = true;
+        }
+        // 单字段弹窗（重命名、插入数组元素、排序字段），Enter 或 Ctrl-S 确认
+        CurrentScreen::Editing if app.prompt.is_some() => match key.code {
+            KeyCode::Enter => {
+                app.submit_prompt();
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.submit_prompt();
+            }
+            KeyCode::Esc => {
+                app.cancel_prompt();
+            }
+            KeyCode::Backspace => match app.currently_editing {
+                Some(CurrentlyEditing::Value) => {
+                    app.value_input.pop();
+                }
+                _ => {
+                    app.key_input.pop();
+                }
+            },
+            KeyCode::Char(value) => match app.currently_editing {
+                Some(CurrentlyEditing::Value) => app.value_input.push(value),
+                _ => app.key_input.push(value),
+            },
+            _ => {}
+        },
+        CurrentScreen::Editing => match key.code {
+            // Ctrl-Enter 需要终端支持键盘增强协议，Ctrl-S 作为通用的确认键
+            KeyCode::Enter if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.save_key_value();
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.save_key_value();
+            }
+            // Ctrl-T 在 Key/Value 框之间切换，Tab 留给补全
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.toggle_editing();
+                app.suggestion = 0;
+            }
+            // 补全列表显示时 Enter 选用候选
+            KeyCode::Enter if app.accept_suggestion() => {}
+            KeyCode::Enter => {
+                // 用户在编辑界面按下Enter要判断,
+                // 1. 如果是编辑key则进入编辑value界面
+                // 2. 如果是编辑value则插入换行
+                if let Some(editing) = &app.currently_editing {
+                    match editing {
+                        CurrentlyEditing::Key => {
+                            app.currently_editing = Some(CurrentlyEditing::Value);
+                            app.suggestion = 0;
+                        },
+                        CurrentlyEditing::Value => {
+                            app.value_input.push('\n');
+                            app.value_scroll = 0;
                         }
-                    },
-                    KeyCode::Backspace => {
-                        if let Some(editing) = &app.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Key => {
-                                    app.key_input.pop();
-                                },
-                                CurrentlyEditing::Value => {
-                                    app.value_input.pop();
-                                }
-                            }
+                    }
+                }
+            },
+            KeyCode::Backspace => {
+                app.suggestion = 0;
+                if let Some(editing) = &app.currently_editing {
+                    match editing {
+                        CurrentlyEditing::Key => {
+                            app.key_input.pop();
+                        },
+                        CurrentlyEditing::Value => {
+                            app.value_input.pop();
+                            app.value_scroll = 0;
                         }
-                    },
-                    KeyCode::Esc => {
-                        app.current_screen = CurrentScreen::Main;
-                        app.currently_editing = None;
-                    },
-                    KeyCode::Tab => {
-                        app.toggle_editing();
                     }
-                    KeyCode::Char(value) => {
-                        if let Some(editing) = &app.currently_editing { 
-                            match editing {
-                                CurrentlyEditing::Key => {
-                                    app.key_input.push(value)
-                                },
-                                CurrentlyEditing::Value => {
-                                    app.value_input.push(value);
-                                }
-                            }
+                }
+            },
+            // 补全列表显示时上下选择候选，否则在 Value 框内上下滚动
+            KeyCode::Up if !app.suggestions().is_empty() => {
+                app.move_suggestion(false);
+            }
+            KeyCode::Down if !app.suggestions().is_empty() => {
+                app.move_suggestion(true);
+            }
+            KeyCode::Up => {
+                if let Some(CurrentlyEditing::Value) = &app.currently_editing {
+                    app.value_scroll += 1;
+                }
+            }
+            KeyCode::Down => {
+                if let Some(CurrentlyEditing::Value) = &app.currently_editing {
+                    app.value_scroll = app.value_scroll.saturating_sub(1);
+                }
+            }
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+                app.currently_editing = None;
+                app.suggestion = 0;
+                app.accepted = None;
+            },
+            KeyCode::Tab => {
+                app.accept_suggestion();
+            }
+            KeyCode::Char(value) => {
+                app.suggestion = 0;
+                if let Some(editing) = &app.currently_editing { 
+                    match editing {
+                        CurrentlyEditing::Key => {
+                            app.key_input.push(value)
+                        },
+                        CurrentlyEditing::Value => {
+                            app.value_input.push(value);
+                            app.value_scroll = 0;
                         }
-                    },
-                    _ => {}
+                    }
                 }
+            },
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// 不启动终端界面，对每个文件回放宏后写回；宏中关闭的标签页按退出时的选择输出
+fn run_headless(tabs: &mut Tabs, register: char, times: usize) -> Result<(), Box<dyn Error>> {
+    if tabs.macros.get(register).is_none() {
+        return Err(format!("macro @{register} is not recorded").into());
+    }
+    while !tabs.is_empty() {
+        replay(tabs, register, times)?;
+        // 宏没有关闭标签页时保存它的修改，再处理下一个文件；没改动的文件不重写
+        if !tabs.is_empty() {
+            let mut app = tabs.apps.remove(tabs.active);
+            tabs.active = 0;
+            app.close_record();
+            if app.is_modified() {
+                app.save_file()?;
             }
         }
     }
+    for (mut app, do_print) in tabs.closed.drain(..) {
+        if do_print {
+            app.close_record();
+            app.print_json()?;
+            if app.is_modified() {
+                app.save_file()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 无界面执行 `--set`/`--delete`/`--rename`，与命令行中的同名命令使用相同的文档操作
+/// 任何一步失败都不写出文件
+fn run_script(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.paths[0].clone();
+    if args.ndjson || ndjson::is_ndjson_path(&path) {
+        return Err("scripted edits need a JSON object document, not JSON Lines".into());
+    }
+    // App::open 把不存在的文件当作新建，文件名写错时不能悄悄创建新文件
+    if let Err(err) = std::fs::metadata(&path) {
+        return Err(format!("{}: {err}", path.display()).into());
+    }
+    let mut app = App::open(path)?;
+    for (command, argument) in &args.edits {
+        command::run(&mut app, command, argument)
+            .map_err(|err| format!("--{command} {argument}: {err}"))?;
+    }
+    if let Some(output) = &args.output {
+        app.input_path = Some(output.clone());
+    }
+    app.save_file()?;
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    enable_raw_mode();
-    let mut stderr = io::stderr();
-    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    let args = match Args::parse(std::env::args_os().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+    if !args.edits.is_empty() {
+        if let Err(err) = run_script(&args) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // 无界面回放时不询问恢复日志，也不改动它
+    let headless = args.replay.is_some();
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("using the default config: {err}");
+        Config::default()
+    });
+    let schema = match &args.schema {
+        Some(path) => match Schema::load(path) {
+            Ok(schema) => Some(Rc::new(schema)),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    // 每个输入文件一个标签页，恢复日志按路径区分；过大的文件以只读延迟加载模式打开
+    let mut apps = Vec::new();
+    for path in args.paths {
+        let mut app = open_document(path, args.ndjson, args.lazy)?;
+        app.config = config.clone();
+        app.schema = schema.clone();
+        if !headless {
+            app.watch();
+            app.check_recovery();
+        }
+        apps.push(app);
+    }
+    if apps.is_empty() {
+        let mut app = App::new();
+        app.config = config;
+        app.schema = schema;
+        app.watch();
+        app.check_recovery();
+        apps.push(app);
+    }
+    let mut tabs = Tabs::new(apps);
+    tabs.macros = match Macros::load() {
+        Ok(macros) => macros,
+        Err(err) if !headless => {
+            eprintln!("ignoring saved macros: {err}");
+            Macros::default()
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    };
+    if let Some(register) = args.replay {
+        if let Err(err) = run_headless(&mut tabs, register, args.repeat) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    enable_raw_mode()?;
+    // 支持键盘增强协议的终端才能区分 Ctrl-Enter 与 Enter
+    let keyboard_enhancement = supports_keyboard_enhancement().unwrap_or(false);
+    enter_terminal(keyboard_enhancement)?;
 
-    let backend = CrosstermBackend::new(stderr);
+    let backend = CrosstermBackend::new(io::stderr());
     let mut terminal = Terminal::new(backend)?;
-    let mut app = App::new();
-    let res = run_app(&mut terminal, &mut app);
+    let res = run_app(&mut terminal, &mut tabs, keyboard_enhancement);
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    if let Ok(do_print) = res {
+    leave_terminal(keyboard_enhancement)?;
+
+    // 按关闭顺序输出并保存各个文档
+    for (mut app, do_print) in tabs.closed {
         if do_print {
+            app.close_record();
             app.print_json()?;
+            // 关闭后不再监听文件，通过 write 在写入前再检查一次外部改写
+            if app.input_path.is_some() {
+                if let Err(err) = app.write(None) {
+                    // 没有保存的修改留在恢复日志中，下次打开时可以恢复
+                    eprintln!(
+                        "{}: not saved ({err}); reopen it to recover your edits",
+                        app.title()
+                    );
+                    app.dirty = true;
+                    app.autosave()?;
+                    continue;
+                }
+            }
         }
-    } else if let Err(err) = res {
+        // 正常关闭，恢复日志不再需要
+        app.discard_journal()?;
+    }
+    if let Err(err) = res {
         println!("{err:?}");
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 与 run_app 相同地处理按键：宏按键之外的按键先录制再分发
+    fn press(tabs: &mut Tabs, keys: &str) {
+        for key in macros::decode(keys).unwrap() {
+            if !handle_macro_key(tabs, key).unwrap() {
+                tabs.macros.record(key);
+                dispatch(tabs, key).unwrap();
+            }
+        }
+    }
+
+    fn keys(tabs: &Tabs) -> Vec<&str> {
+        tabs.current().pairs.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn record_and_replay_macro() {
+        // 录制结束时宏写入配置目录，测试中写到临时目录
+        let dir = std::env::temp_dir()
+            .join(format!("ratatui-json-editor-test-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", &dir);
+        let mut app = App::new();
+        app.pairs = serde_json::from_str(r#"{"a":1,"b":2,"c":3,"d":4,"e":5}"#).unwrap();
+        app.history = vec![app.pairs.clone()];
+        let mut tabs = Tabs::new(vec![app]);
+
+        // 录制删除选中成员的宏，录制时按键照常生效
+        press(&mut tabs, "qaxq");
+        assert_eq!(keys(&tabs), ["b", "c", "d", "e"]);
+        assert!(tabs.macros.recording.is_none());
+        press(&mut tabs, "@a");
+        assert_eq!(keys(&tabs), ["c", "d", "e"]);
+        press(&mut tabs, "2@a");
+        assert_eq!(keys(&tabs), ["e"]);
+
+        let saved = Macros::load().unwrap();
+        assert_eq!(macros::encode(saved.get('a').unwrap()), "x");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
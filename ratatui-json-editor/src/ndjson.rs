@@ -0,0 +1,146 @@
+use crate::document::Pairs;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// JSON Lines 文档：每行一条记录，保存时未修改的行按原样写回
+pub struct LinesDocument {
+    lines: Vec<String>,            // 原始行内容，包含行尾的换行符
+    edited: BTreeMap<usize, Pairs>, // 被修改过的记录，保存时重新序列化
+    pub selected: usize,
+    pub open: Option<usize>, // 当前在结构化编辑器中打开的记录
+}
+
+/// 文件扩展名是否表示 JSON Lines
+pub fn is_ndjson_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("ndjson") | Some("jsonl")
+    )
+}
+
+impl LinesDocument {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(LinesDocument::parse(&content))
+    }
+
+    fn parse(content: &str) -> Self {
+        LinesDocument {
+            lines: content.split_inclusive('\n').map(String::from).collect(),
+            edited: BTreeMap::new(),
+            selected: 0,
+            open: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        !self.edited.is_empty()
+    }
+
+    pub fn is_edited(&self, index: usize) -> bool {
+        self.edited.contains_key(&index)
+    }
+
+    /// 列表中显示的行内容，已修改的行显示修改后的 json
+    pub fn preview(&self, index: usize) -> String {
+        match self.edited.get(&index) {
+            Some(record) => serde_json::to_string(record).unwrap_or_default(),
+            None => self.lines[index].trim_end_matches(['\r', '\n']).to_string(),
+        }
+    }
+
+    /// 解析一条记录，只有 json 对象可以在结构化编辑器中打开
+    pub fn record(&self, index: usize) -> Result<Pairs, String> {
+        if let Some(record) = self.edited.get(&index) {
+            return Ok(record.clone());
+        }
+        let line = self.lines.get(index).ok_or("no such line")?;
+        serde_json::from_str(line).map_err(|err| format!("line {}: {err}", index + 1))
+    }
+
+    /// 记录编辑结果，与原内容相同时视为未修改
+    pub fn update(&mut self, index: usize, record: Pairs) {
+        let unchanged = serde_json::from_str::<Pairs>(&self.lines[index])
+            .is_ok_and(|original| original == record);
+        if unchanged {
+            self.edited.remove(&index);
+        } else {
+            self.edited.insert(index, record);
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.lines.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// 逐行写回，未修改的行保持原有字节，修改过的行沿用原行尾
+    pub fn serialize(&self) -> serde_json::Result<String> {
+        let mut output = String::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            match self.edited.get(&index) {
+                Some(record) => {
+                    let body = line.trim_end_matches(['\r', '\n']);
+                    output.push_str(&serde_json::to_string(record)?);
+                    output.push_str(&line[body.len()..]);
+                }
+                None => output.push_str(line),
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: &str = "{ \"id\" : 1, \"name\": \"a\" }\r\n\nnot json\n{\"id\":2,\"n\":1.50}\n{\"id\":3}";
+
+    #[test]
+    fn unchanged_lines_are_written_back_verbatim() {
+        let document = LinesDocument::parse(LINES);
+        assert_eq!(document.len(), 5);
+        assert_eq!(document.serialize().unwrap(), LINES);
+        assert!(document.record(2).is_err());
+    }
+
+    #[test]
+    fn edited_records_keep_their_line_endings() {
+        let mut document = LinesDocument::parse(LINES);
+        let mut first = document.record(0).unwrap();
+        first.insert(String::from("ok"), true.into());
+        document.update(0, first);
+        let mut last = document.record(4).unwrap();
+        last.remove("id");
+        document.update(4, last);
+        assert_eq!(
+            document.serialize().unwrap(),
+            "{\"id\":1,\"name\":\"a\",\"ok\":true}\r\n\nnot json\n{\"id\":2,\"n\":1.50}\n{}"
+        );
+    }
+
+    #[test]
+    fn update_with_the_original_record_is_not_a_change() {
+        let mut document = LinesDocument::parse(LINES);
+        let record = document.record(3).unwrap();
+        document.update(3, record);
+        assert!(!document.is_modified());
+        assert_eq!(document.serialize().unwrap(), LINES);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::document::{self, Container, Pairs, Segment};
+use regex::Regex;
+use serde_json::Value;
+
+/// 替换的对象：key、字符串值，或两者
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scope {
+    Both,
+    Keys,
+    Values,
+}
+
+impl Scope {
+    pub fn next(self) -> Scope {
+        match self {
+            Scope::Both => Scope::Keys,
+            Scope::Keys => Scope::Values,
+            Scope::Values => Scope::Both,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::Both => "keys and values",
+            Scope::Keys => "keys",
+            Scope::Values => "values",
+        }
+    }
+
+    fn keys(self) -> bool {
+        self != Scope::Values
+    }
+
+    fn values(self) -> bool {
+        self != Scope::Keys
+    }
+}
+
+/// 替换对话框中获得焦点的部分
+#[derive(PartialEq)]
+pub enum Focus {
+    Find,
+    Replacement,
+    Matches,
+}
+
+/// 一处匹配：is_key 为 true 时替换 path 最后一段的 key，否则替换 path 处的字符串值
+pub struct Match {
+    pub path: Vec<Segment>,
+    pub is_key: bool,
+    pub before: String,
+    pub after: String,
+    pub accepted: bool,
+}
+
+/// 批量查找替换的对话框状态，输入变化时重新列出匹配项供逐项确认
+pub struct Replace {
+    pub find: String,
+    pub replacement: String,
+    pub focus: Focus,
+    pub regex: bool,  // 按正则表达式匹配，替换文本中可以用 `$1`、`${name}` 引用分组
+    pub scope: Scope, // 替换 key、值还是两者
+    pub whole_document: bool, // false 时只在当前所在的容器内替换
+    pub matches: Vec<Match>,
+    pub selected: usize,
+    pub error: Option<String>, // 正则表达式无效时的错误
+}
+
+impl Replace {
+    pub fn new() -> Replace {
+        Replace {
+            find: String::new(),
+            replacement: String::new(),
+            focus: Focus::Find,
+            regex: false,
+            scope: Scope::Both,
+            whole_document: false,
+            matches: Vec::new(),
+            selected: 0,
+            error: None,
+        }
+    }
+
+    /// 正在输入的框
+    pub fn input_mut(&mut self) -> &mut String {
+        match self.focus {
+            Focus::Replacement => &mut self.replacement,
+            _ => &mut self.find,
+        }
+    }
+
+    /// 按当前条件重新列出匹配项，全部默认接受；subtree 为当前所在容器的路径
+    pub fn search(&mut self, pairs: &Pairs, subtree: &[Segment]) {
+        self.matches.clear();
+        self.selected = 0;
+        self.error = None;
+        if self.find.is_empty() {
+            return;
+        }
+        let pattern = if self.regex {
+            match Regex::new(&self.find) {
+                Ok(pattern) => Some(pattern),
+                Err(err) => {
+                    self.error = Some(err.to_string());
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        let replace = |text: &str| match &pattern {
+            Some(pattern) => pattern.is_match(text).then(|| {
+                pattern
+                    .replace_all(text, self.replacement.as_str())
+                    .into_owned()
+            }),
+            None => text
+                .contains(self.find.as_str())
+                .then(|| text.replace(self.find.as_str(), &self.replacement)),
+        };
+        let root = if self.whole_document { &[] } else { subtree };
+        let mut path = root.to_vec();
+        let mut matches = Vec::new();
+        if let Some(container) = document::container(pairs, root) {
+            let members = match container {
+                Container::Object(map) => map
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value))
+                    .collect(),
+                Container::Array(items) => items.iter().map(|value| (None, value)).collect(),
+            };
+            collect(members, self.scope, &replace, &mut path, &mut matches);
+        }
+        self.matches = matches;
+    }
+
+    /// 接受的匹配项数
+    pub fn accepted(&self) -> usize {
+        self.matches.iter().filter(|found| found.accepted).count()
+    }
+}
+
+/// 逐个检查容器的成员，key 为 None 表示数组元素
+fn collect(
+    members: Vec<(Option<&str>, &Value)>,
+    scope: Scope,
+    replace: &dyn Fn(&str) -> Option<String>,
+    path: &mut Vec<Segment>,
+    matches: &mut Vec<Match>,
+) {
+    for (index, (key, value)) in members.into_iter().enumerate() {
+        path.push(match key {
+            Some(key) => Segment::Key(key.to_string()),
+            None => Segment::Index(index),
+        });
+        if let Some(key) = key.filter(|_| scope.keys()) {
+            if let Some(after) = replace(key).filter(|after| after != key) {
+                matches.push(Match {
+                    path: path.clone(),
+                    is_key: true,
+                    before: key.to_string(),
+                    after,
+                    accepted: true,
+                });
+            }
+        }
+        match value {
+            Value::String(text) if scope.values() => {
+                if let Some(after) = replace(text).filter(|after| after != text) {
+                    matches.push(Match {
+                        path: path.clone(),
+                        is_key: false,
+                        before: text.clone(),
+                        after,
+                        accepted: true,
+                    });
+                }
+            }
+            Value::Object(map) => {
+                let members = map
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value))
+                    .collect();
+                collect(members, scope, replace, path, matches);
+            }
+            Value::Array(items) => {
+                let members = items.iter().map(|value| (None, value)).collect();
+                collect(members, scope, replace, path, matches);
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// 在文档副本上应用接受的替换：先替换值，再从最深处开始改 key，保证其余路径仍然有效
+/// 返回替换的处数和因 key 冲突而跳过的路径
+pub fn apply(pairs: &mut Pairs, matches: &[Match]) -> (usize, Vec<String>) {
+    let accepted: Vec<&Match> = matches.iter().filter(|found| found.accepted).collect();
+    let mut applied = 0;
+    let mut skipped = Vec::new();
+    for found in accepted.iter().filter(|found| !found.is_key) {
+        let value = Value::String(found.after.clone());
+        match document::set_path(pairs, &found.path, value) {
+            Ok(()) => applied += 1,
+            Err(_) => skipped.push(document::json_path(&found.path)),
+        }
+    }
+    let mut renames: Vec<&&Match> = accepted.iter().filter(|found| found.is_key).collect();
+    renames.sort_by_key(|found| std::cmp::Reverse(found.path.len()));
+    for found in renames {
+        match document::rename_path(pairs, &found.path, found.after.clone()) {
+            Ok(()) => applied += 1,
+            Err(_) => skipped.push(document::json_path(&found.path)),
+        }
+    }
+    (applied, skipped)
+}
@@ -0,0 +1,238 @@
+use crate::document::Segment;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+/// 递归生成骨架对象的最大深度，避免自引用的 schema 无限展开
+const MAX_DEPTH: usize = 8;
+
+/// 通过 `--schema` 加载的 JSON Schema，只使用 properties/items/required/default/examples 等常用关键字
+pub struct Schema {
+    root: Value,
+}
+
+impl Schema {
+    pub fn load(path: &Path) -> Result<Schema, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let root =
+            serde_json::from_str(&content).map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(Schema { root })
+    }
+
+    /// 展开文档内的 `$ref`（例如 `#/definitions/service`、`#/$defs/service`）
+    pub fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        let mut schema = schema;
+        for _ in 0..MAX_DEPTH {
+            let pointer = schema
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'));
+            match pointer.and_then(|pointer| self.root.pointer(pointer)) {
+                Some(target) => schema = target,
+                None => break,
+            }
+        }
+        schema
+    }
+
+    /// 文档中某个位置对应的子 schema，schema 没有描述该位置时为 None
+    pub fn at(&self, path: &[Segment]) -> Option<&Value> {
+        let mut schema = self.resolve(&self.root);
+        for segment in path {
+            schema = self.resolve(child(schema, segment)?);
+        }
+        Some(schema)
+    }
+
+    /// 新成员的候选值：default、每个 examples，都没有时按 required 字段生成骨架对象
+    pub fn samples(&self, schema: &Value) -> Vec<(String, Value)> {
+        let schema = self.resolve(schema);
+        let mut samples = Vec::new();
+        if let Some(default) = schema.get("default") {
+            samples.push((String::from("default"), default.clone()));
+        }
+        if let Some(Value::Array(examples)) = schema.get("examples") {
+            for (index, example) in examples.iter().enumerate() {
+                samples.push((format!("example {}", index + 1), example.clone()));
+            }
+        }
+        if samples.is_empty() && schema.get("required").is_some() {
+            samples.push((String::from("required fields"), self.skeleton(schema, 0)));
+        }
+        samples
+    }
+
+    /// 按 schema 生成占位值：对象只包含 required 字段，字段优先使用 default/examples/const/enum
+    fn skeleton(&self, schema: &Value, depth: usize) -> Value {
+        let schema = self.resolve(schema);
+        if let Some(value) = schema
+            .get("default")
+            .or_else(|| schema.get("const"))
+            .or_else(|| schema.pointer("/examples/0"))
+            .or_else(|| schema.pointer("/enum/0"))
+        {
+            return value.clone();
+        }
+        let kind = match schema.get("type") {
+            Some(Value::String(kind)) => kind.as_str(),
+            Some(Value::Array(kinds)) => kinds.first().and_then(Value::as_str).unwrap_or(""),
+            _ if schema.get("properties").is_some() => "object",
+            _ => "",
+        };
+        match kind {
+            "string" => Value::String(String::new()),
+            "integer" | "number" => Value::from(0),
+            "boolean" => Value::Bool(false),
+            "array" => Value::Array(Vec::new()),
+            "object" if depth < MAX_DEPTH => {
+                let mut object = Map::new();
+                let required = schema.get("required").and_then(Value::as_array);
+                for name in required.into_iter().flatten().filter_map(Value::as_str) {
+                    let property = schema
+                        .get("properties")
+                        .and_then(|properties| properties.get(name))
+                        .unwrap_or(&Value::Null);
+                    object.insert(name.to_string(), self.skeleton(property, depth + 1));
+                }
+                Value::Object(object)
+            }
+            "object" => Value::Object(Map::new()),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// 成员的 schema：对象先查 properties 再查 additionalProperties，数组查 items
+pub fn child<'a>(schema: &'a Value, segment: &Segment) -> Option<&'a Value> {
+    match segment {
+        Segment::Key(key) => schema
+            .get("properties")
+            .and_then(|properties| properties.get(key))
+            .or_else(|| {
+                schema
+                    .get("additionalProperties")
+                    .filter(|schema| schema.is_object())
+            }),
+        Segment::Index(_) => schema.get("items").filter(|schema| schema.is_object()),
+    }
+}
+
+/// 推断时字符串取值不超过该数量且有重复时生成 enum
+const ENUM_LIMIT: usize = 5;
+
+/// 根据示例文档推断 JSON Schema：类型、所有示例都有的 required 字段、低基数字符串的 enum 和数组元素的结构
+pub fn infer(samples: &[&Value]) -> Value {
+    let mut schema = Map::new();
+    schema.insert(
+        String::from("$schema"),
+        Value::from("https://json-schema.org/draft/2020-12/schema"),
+    );
+    if let Value::Object(inferred) = infer_all(samples) {
+        schema.extend(inferred);
+    }
+    Value::Object(schema)
+}
+
+/// 同一位置上所有示例值的 schema，类型不止一种时用 anyOf 列出
+fn infer_all(samples: &[&Value]) -> Value {
+    let mut groups: Vec<(&str, Vec<&Value>)> = Vec::new();
+    for &sample in samples {
+        let kind = match sample {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(number) if number.is_f64() => "number",
+            Value::Number(_) => "integer",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        match groups.iter_mut().find(|(existing, _)| *existing == kind) {
+            Some((_, values)) => values.push(sample),
+            None => groups.push((kind, vec![sample])),
+        }
+    }
+    // 整数和小数混用时统一为 number
+    if let Some(integers) = groups.iter().position(|(kind, _)| *kind == "integer") {
+        if let Some(numbers) = groups.iter().position(|(kind, _)| *kind == "number") {
+            let (_, values) = groups.remove(integers);
+            let numbers = if numbers > integers {
+                numbers - 1
+            } else {
+                numbers
+            };
+            groups[numbers].1.extend(values);
+        }
+    }
+    let mut schemas: Vec<Value> = groups
+        .iter()
+        .map(|(kind, values)| infer_kind(kind, values))
+        .collect();
+    match schemas.len() {
+        0 => Value::Object(Map::new()),
+        1 => schemas.remove(0),
+        _ => {
+            let mut schema = Map::new();
+            schema.insert(String::from("anyOf"), Value::Array(schemas));
+            Value::Object(schema)
+        }
+    }
+}
+
+fn infer_kind(kind: &str, values: &[&Value]) -> Value {
+    let mut schema = Map::new();
+    schema.insert(String::from("type"), Value::from(kind));
+    match kind {
+        "object" => {
+            let objects: Vec<&Map<String, Value>> = values
+                .iter()
+                .filter_map(|value| value.as_object())
+                .collect();
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for object in &objects {
+                for key in object.keys() {
+                    if properties.contains_key(key) {
+                        continue;
+                    }
+                    let members: Vec<&Value> = objects
+                        .iter()
+                        .filter_map(|object| object.get(key))
+                        .collect();
+                    if members.len() == objects.len() {
+                        required.push(Value::from(key.as_str()));
+                    }
+                    properties.insert(key.clone(), infer_all(&members));
+                }
+            }
+            schema.insert(String::from("properties"), Value::Object(properties));
+            if !required.is_empty() {
+                schema.insert(String::from("required"), Value::Array(required));
+            }
+        }
+        "array" => {
+            let items: Vec<&Value> = values
+                .iter()
+                .filter_map(|value| value.as_array())
+                .flatten()
+                .collect();
+            if !items.is_empty() {
+                schema.insert(String::from("items"), infer_all(&items));
+            }
+        }
+        "string" => {
+            let mut distinct: Vec<&Value> = Vec::new();
+            for &value in values {
+                if !distinct.contains(&value) {
+                    distinct.push(value);
+                }
+            }
+            if distinct.len() <= ENUM_LIMIT && distinct.len() < values.len() {
+                let choices = distinct.into_iter().cloned().collect();
+                schema.insert(String::from("enum"), Value::Array(choices));
+            }
+        }
+        _ => {}
+    }
+    Value::Object(schema)
+}
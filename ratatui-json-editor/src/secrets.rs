@@ -0,0 +1,374 @@
+use crate::document::{self, Pairs, Segment};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// 隐藏的值显示为该占位符，恢复日志中也用它代替机密值
+pub const MASK: &str = "••••";
+/// 恢复日志中无法从原文件取回的机密值的占位符
+const LOST: &str = "••••?";
+
+/// `*` 通配的模式匹配，不区分大小写
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    // 记录最近一个 `*` 的位置，匹配失败时让它多吞一个字符
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// key 是否匹配任一机密模式
+pub fn is_secret(key: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| glob_match(pattern, key))
+}
+
+/// 值中是否嵌套着机密 key
+fn contains_secret(value: &Value, patterns: &[String]) -> bool {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .any(|(key, value)| is_secret(key, patterns) || contains_secret(value, patterns)),
+        Value::Array(items) => items.iter().any(|item| contains_secret(item, patterns)),
+        _ => false,
+    }
+}
+
+/// 把嵌套的机密值替换为占位符，没有机密时不复制
+pub fn mask<'a>(value: &'a Value, patterns: &[String]) -> Cow<'a, Value> {
+    if !contains_secret(value, patterns) {
+        return Cow::Borrowed(value);
+    }
+    let mut masked = value.clone();
+    mask_in_place(&mut masked, patterns);
+    Cow::Owned(masked)
+}
+
+fn mask_in_place(value: &mut Value, patterns: &[String]) {
+    match value {
+        Value::Object(map) => mask_pairs(map, patterns),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| mask_in_place(item, patterns)),
+        _ => {}
+    }
+}
+
+pub fn mask_pairs(pairs: &mut Pairs, patterns: &[String]) {
+    for (key, value) in pairs.iter_mut() {
+        if is_secret(key, patterns) {
+            *value = Value::String(MASK.to_string());
+        } else {
+            mask_in_place(value, patterns);
+        }
+    }
+}
+
+/// 预览用的 json 文本中隐藏机密值：能完整解析时按值处理，否则（被截断等）逐个记号处理
+pub fn mask_text<'a>(text: &'a str, patterns: &[String]) -> Cow<'a, str> {
+    if patterns.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let Ok(value) = serde_json::from_str::<Value>(text) else {
+        return Cow::Owned(mask_tokens(text, patterns));
+    };
+    match mask(&value, patterns) {
+        Cow::Borrowed(_) => Cow::Borrowed(text),
+        Cow::Owned(masked) if text.trim_end().contains('\n') => {
+            Cow::Owned(serde_json::to_string_pretty(&masked).unwrap_or_default())
+        }
+        Cow::Owned(masked) => Cow::Owned(masked.to_string()),
+    }
+}
+
+/// 逐个记号扫描可能不完整的 json 文本，机密 key 之后的值（对象和数组整个）替换为占位符
+fn mask_tokens(text: &str, patterns: &[String]) -> String {
+    let placeholder = Value::String(MASK.to_string()).to_string();
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut secret = false; // 上一个 key 是机密，其后的值需要隐藏
+    while let Some(c) = rest.chars().next() {
+        let punctuation = c.is_whitespace() || ",:{}[]".contains(c);
+        let len = match c {
+            '"' => string_len(rest),
+            '{' | '[' if secret => container_len(rest),
+            _ if punctuation => c.len_utf8(),
+            _ => rest
+                .find(|c: char| c.is_whitespace() || ",:{}[]\"".contains(c))
+                .unwrap_or(rest.len()),
+        };
+        let (token, after) = rest.split_at(len);
+        rest = after;
+        if c == '"' && rest.trim_start().starts_with(':') {
+            secret =
+                serde_json::from_str::<String>(token).is_ok_and(|key| is_secret(&key, patterns));
+            result.push_str(token);
+        } else if secret && (!punctuation || c == '{' || c == '[') {
+            result.push_str(&placeholder);
+            secret = false;
+        } else {
+            result.push_str(token);
+        }
+    }
+    result
+}
+
+/// 从开头的引号到字符串结束的长度，字符串被截断时到文本末尾
+fn string_len(text: &str) -> usize {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return index + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// 从开头的括号到与之配对的括号的长度，被截断时到文本末尾
+fn container_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut index = 0;
+    while let Some(c) = text[index..].chars().next() {
+        match c {
+            '"' => {
+                index += string_len(&text[index..]);
+                continue;
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => {}
+        }
+        index += c.len_utf8();
+    }
+    text.len()
+}
+
+/// 写入恢复日志前隐藏机密值：与 saved（文件中的版本）同一位置的值相同的写为 MASK，恢复时可以从文件取回；
+/// 新增或改过的机密值无法取回，写为 LOST
+pub fn mask_for_journal(pairs: &mut Pairs, saved: Option<&Pairs>, patterns: &[String]) {
+    for (key, value) in pairs.iter_mut() {
+        let saved = saved.and_then(|saved| saved.get(key));
+        if is_secret(key, patterns) {
+            let placeholder = if saved == Some(&*value) { MASK } else { LOST };
+            *value = Value::String(placeholder.to_string());
+        } else {
+            mask_value_for_journal(value, saved, patterns);
+        }
+    }
+}
+
+fn mask_value_for_journal(value: &mut Value, saved: Option<&Value>, patterns: &[String]) {
+    match value {
+        Value::Object(map) => mask_for_journal(map, saved.and_then(Value::as_object), patterns),
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                mask_value_for_journal(item, saved.and_then(|saved| saved.get(index)), patterns);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 从恢复日志还原机密值：MASK 换回原文件中同一位置的值，LOST 或原文件中已没有的值保留 MASK 占位符，
+/// 返回这些无法还原的值的路径
+pub fn unmask_pairs(pairs: &mut Pairs, original: &Pairs, patterns: &[String]) -> Vec<String> {
+    let mut lost = Vec::new();
+    unmask_object(pairs, Some(original), patterns, &mut Vec::new(), &mut lost);
+    lost
+}
+
+fn unmask_object(
+    pairs: &mut Pairs,
+    original: Option<&Pairs>,
+    patterns: &[String],
+    path: &mut Vec<Segment>,
+    lost: &mut Vec<String>,
+) {
+    for (key, value) in pairs.iter_mut() {
+        let original = original.and_then(|original| original.get(key));
+        path.push(Segment::Key(key.clone()));
+        let placeholder = value.as_str().filter(|text| *text == MASK || *text == LOST);
+        match (placeholder, original) {
+            (Some(MASK), Some(original)) if is_secret(key, patterns) => *value = original.clone(),
+            (Some(_), _) if is_secret(key, patterns) => {
+                *value = Value::String(MASK.to_string());
+                lost.push(document::json_path(path));
+            }
+            _ => unmask(value, original, patterns, path, lost),
+        }
+        path.pop();
+    }
+}
+
+fn unmask(
+    value: &mut Value,
+    original: Option<&Value>,
+    patterns: &[String],
+    path: &mut Vec<Segment>,
+    lost: &mut Vec<String>,
+) {
+    match value {
+        Value::Object(map) => unmask_object(
+            map,
+            original.and_then(Value::as_object),
+            patterns,
+            path,
+            lost,
+        ),
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push(Segment::Index(index));
+                unmask(
+                    item,
+                    original.and_then(|original| original.get(index)),
+                    patterns,
+                    path,
+                    lost,
+                );
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 仍是占位符的机密值的路径，这些值不能写入文件
+pub fn placeholders(pairs: &Pairs, patterns: &[String]) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut path = Vec::new();
+    find_placeholders(pairs, patterns, &mut path, &mut found);
+    found
+}
+
+fn find_placeholders(
+    pairs: &Pairs,
+    patterns: &[String],
+    path: &mut Vec<Segment>,
+    found: &mut Vec<String>,
+) {
+    for (key, value) in pairs {
+        path.push(Segment::Key(key.clone()));
+        if is_secret(key, patterns) && value.as_str() == Some(MASK) {
+            found.push(document::json_path(path));
+        } else {
+            find_placeholders_in(value, patterns, path, found);
+        }
+        path.pop();
+    }
+}
+
+fn find_placeholders_in(
+    value: &Value,
+    patterns: &[String],
+    path: &mut Vec<Segment>,
+    found: &mut Vec<String>,
+) {
+    match value {
+        Value::Object(map) => find_placeholders(map, patterns, path, found),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(Segment::Index(index));
+                find_placeholders_in(item, patterns, path, found);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{self, Journal};
+
+    fn patterns() -> Vec<String> {
+        ["*password*", "*token*"].map(String::from).to_vec()
+    }
+
+    fn pairs(json: &str) -> Pairs {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// 经过恢复日志的写入和读取，再从 original 还原机密值
+    fn recover(current: &Pairs, saved: &Pairs, original: &Pairs) -> (Pairs, Vec<String>) {
+        let mut masked = current.clone();
+        mask_for_journal(&mut masked, Some(saved), &patterns());
+        let path = std::env::temp_dir().join(format!(
+            "ratatui-json-editor-test-{}-{:?}.journal.json",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        Journal {
+            input_path: None,
+            saved_at: journal::now(),
+            pairs: masked,
+            history_index: 1,
+            masked: true,
+        }
+        .write(&path)
+        .unwrap();
+        let mut found = Journal::read(&path).unwrap();
+        journal::remove(&path).unwrap();
+        assert!(!serde_json::to_string(&found.pairs)
+            .unwrap()
+            .contains("hunter2"));
+        let lost = unmask_pairs(&mut found.pairs, original, &patterns());
+        (found.pairs, lost)
+    }
+
+    #[test]
+    fn unchanged_secrets_are_restored_from_the_file() {
+        let saved = pairs(r#"{"db":{"password":"hunter2","host":"h"},"keys":[{"token":"t1"}]}"#);
+        let mut current = saved.clone();
+        current.insert(String::from("name"), Value::from("edited"));
+        let (restored, lost) = recover(&current, &saved, &saved);
+        assert!(lost.is_empty());
+        assert_eq!(restored, current);
+        assert!(placeholders(&restored, &patterns()).is_empty());
+    }
+
+    #[test]
+    fn edited_secrets_are_reported_and_cannot_be_saved() {
+        let saved = pairs(r#"{"password":"hunter2","keys":[{"token":"t1"},{"token":"t2"}]}"#);
+        // 改过的机密值和删除第一个元素后移位的机密值都不能从文件取回
+        let current = pairs(r#"{"password":"changed","keys":[{"token":"t2"}]}"#);
+        let (restored, lost) = recover(&current, &saved, &saved);
+        assert_eq!(lost, ["$.password", "$.keys[0].token"]);
+        assert_eq!(placeholders(&restored, &patterns()), lost);
+        assert_eq!(restored["password"], MASK);
+        assert_eq!(restored["keys"][0]["token"], MASK);
+    }
+
+    #[test]
+    fn secrets_missing_from_the_file_stay_placeholders() {
+        let saved = pairs(r#"{"password":"hunter2","note":"••••"}"#);
+        let original = pairs(r#"{"note":"••••"}"#);
+        let (restored, lost) = recover(&saved, &saved, &original);
+        assert_eq!(lost, ["$.password"]);
+        // 非机密 key 的值即使与占位符相同也原样保留
+        assert_eq!(restored["note"], MASK);
+        assert_eq!(placeholders(&restored, &patterns()), ["$.password"]);
+    }
+}
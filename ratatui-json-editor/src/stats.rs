@@ -0,0 +1,133 @@
+use crate::document::{self, Pairs, Segment};
+use crate::secrets;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 每个排行榜显示的条数
+const TOP: usize = 5;
+/// 重复值预览的最大字符数
+const PREVIEW_CHARS: usize = 40;
+
+/// 文档统计，按 `print_json` 输出的同一份数据计算
+pub struct Stats {
+    pub counts: Vec<(&'static str, usize)>, // 各类型的节点数，根对象也计入
+    pub max_depth: usize,                   // 最深的成员所在的层数，顶层成员为 1
+    pub largest: Vec<(String, usize)>,      // 成员最多的对象和数组：路径与成员数
+    pub longest: Vec<(String, usize)>,      // 最长的字符串：路径与字符数
+    pub duplicates: Vec<(String, usize)>,   // 出现多次的值：预览与次数
+    pub compact: usize,                     // 紧凑输出的字节数
+    pub pretty: usize,                      // 格式化输出的字节数
+}
+
+/// hidden 为需要隐藏的机密模式，重复值列表中不会出现机密值
+pub fn compute(pairs: &Pairs, hidden: &[String]) -> Stats {
+    let root = Value::Object(pairs.clone());
+    let mut walker = Walker {
+        hidden,
+        counts: [0; 6],
+        max_depth: 0,
+        largest: Vec::new(),
+        longest: Vec::new(),
+        seen: HashMap::new(),
+    };
+    walker.visit(&root, &mut Vec::new());
+
+    let mut duplicates: Vec<(String, usize)> = walker
+        .seen
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .collect();
+    // 出现次数多的在前，次数相同时较长（更值得去重）的在前
+    duplicates.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.len().cmp(&a.0.len())));
+    duplicates.truncate(TOP);
+    let duplicates = duplicates
+        .into_iter()
+        .map(|(text, count)| (preview(&text), count))
+        .collect();
+
+    Stats {
+        counts: KINDS.iter().copied().zip(walker.counts).collect(),
+        max_depth: walker.max_depth,
+        largest: top(walker.largest),
+        longest: top(walker.longest),
+        duplicates,
+        compact: serde_json::to_string(pairs).map_or(0, |text| text.len()),
+        pretty: serde_json::to_string_pretty(pairs).map_or(0, |text| text.len()),
+    }
+}
+
+const KINDS: [&str; 6] = ["object", "array", "string", "number", "boolean", "null"];
+
+struct Walker<'a> {
+    hidden: &'a [String],
+    counts: [usize; 6],
+    max_depth: usize,
+    largest: Vec<(String, usize)>,
+    longest: Vec<(String, usize)>,
+    seen: HashMap<String, usize>, // 值的紧凑 json 文本和出现次数
+}
+
+impl Walker<'_> {
+    fn visit(&mut self, value: &Value, path: &mut Vec<Segment>) {
+        self.max_depth = self.max_depth.max(path.len());
+        let kind = KINDS
+            .iter()
+            .position(|kind| *kind == document::type_name(value))
+            .unwrap_or(KINDS.len() - 1);
+        self.counts[kind] += 1;
+        match value {
+            Value::Object(object) => {
+                self.largest.push((document::json_path(path), object.len()));
+                for (key, member) in object {
+                    path.push(Segment::Key(key.clone()));
+                    self.visit(member, path);
+                    path.pop();
+                }
+            }
+            Value::Array(items) => {
+                self.largest.push((document::json_path(path), items.len()));
+                for (index, member) in items.iter().enumerate() {
+                    path.push(Segment::Index(index));
+                    self.visit(member, path);
+                    path.pop();
+                }
+            }
+            Value::String(text) => {
+                self.longest
+                    .push((document::json_path(path), text.chars().count()));
+            }
+            _ => {}
+        }
+        // 布尔值、null 和空容器重复没有意义，根对象和机密值也不参与
+        let trivial = match value {
+            Value::Null | Value::Bool(_) => true,
+            Value::Object(object) => object.is_empty(),
+            Value::Array(items) => items.is_empty(),
+            _ => false,
+        };
+        let secret =
+            matches!(path.last(), Some(Segment::Key(key)) if secrets::is_secret(key, self.hidden));
+        if !trivial && !secret && !path.is_empty() {
+            let text = secrets::mask(value, self.hidden).to_string();
+            *self.seen.entry(text).or_insert(0) += 1;
+        }
+    }
+}
+
+/// 按数量从大到小取前几项，数量为 0 的不列出
+fn top(mut entries: Vec<(String, usize)>) -> Vec<(String, usize)> {
+    entries.retain(|(_, size)| *size > 0);
+    entries.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    entries.truncate(TOP);
+    entries
+}
+
+fn preview(text: &str) -> String {
+    let text = document::escape_nonprintable(text);
+    if text.chars().count() > PREVIEW_CHARS {
+        let cut: String = text.chars().take(PREVIEW_CHARS - 1).collect();
+        cut + "…"
+    } else {
+        text
+    }
+}
@@ -0,0 +1,100 @@
+use crate::app::App;
+use crate::macros::Macros;
+use serde_json::Value;
+use std::fs;
+
+/// 同时打开的多个文档，每个标签页是一个独立的 App
+pub struct Tabs {
+    pub apps: Vec<App>,
+    pub active: usize,
+    pub clipboard: Option<(String, Value)>, // 在标签页之间复制的键值对
+    pub closed: Vec<(App, bool)>,           // 已关闭的文档及是否输出，退出终端界面后再写出
+    pub macros: Macros,                     // 录制的按键宏，所有标签页共用
+}
+
+impl Tabs {
+    pub fn new(apps: Vec<App>) -> Self {
+        Tabs {
+            apps,
+            active: 0,
+            clipboard: None,
+            closed: Vec::new(),
+            macros: Macros::default(),
+        }
+    }
+
+    pub fn current(&self) -> &App {
+        &self.apps[self.active]
+    }
+
+    pub fn current_mut(&mut self) -> &mut App {
+        &mut self.apps[self.active]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.apps.is_empty()
+    }
+
+    pub fn next(&mut self) {
+        self.active = (self.active + 1) % self.apps.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.active = (self.active + self.apps.len() - 1) % self.apps.len();
+    }
+
+    /// 在新标签页中打开文档并切换过去；同一文件已经打开时直接切换到它
+    pub fn open(&mut self, app: App) {
+        // 比较规范化后的路径，命令行给出的相对路径也能匹配
+        let canonical = |app: &App| {
+            app.input_path
+                .as_ref()
+                .and_then(|path| fs::canonicalize(path).ok())
+        };
+        let existing = canonical(&app).and_then(|path| {
+            self.apps
+                .iter()
+                .position(|open| canonical(open).as_ref() == Some(&path))
+        });
+        match existing {
+            Some(index) => self.active = index,
+            None => {
+                self.apps.push(app);
+                self.active = self.apps.len() - 1;
+            }
+        }
+    }
+
+    /// 关闭当前标签页，do_print 表示退出后是否输出并保存它
+    pub fn close_current(&mut self, do_print: bool) {
+        let app = self.apps.remove(self.active);
+        self.closed.push((app, do_print));
+        if self.active >= self.apps.len() {
+            self.active = self.apps.len().saturating_sub(1);
+        }
+    }
+
+    /// 复制当前标签页选中的键值对
+    pub fn copy_selected(&mut self) {
+        let app = &mut self.apps[self.active];
+        match app.selected_pair() {
+            Some((key, value)) => {
+                self.clipboard = Some((key.clone(), value.clone()));
+                app.status = Some(format!("Copied {key}"));
+            }
+            None => app.status = Some(String::from("Nothing to copy")),
+        }
+    }
+
+    /// 把复制的键值对粘贴到当前标签页，同名 key 会被覆盖
+    pub fn paste(&mut self) {
+        let app = &mut self.apps[self.active];
+        match &self.clipboard {
+            Some((key, value)) => {
+                app.insert_pair(key.clone(), value.clone());
+                app.status = Some(format!("Pasted {key}"));
+            }
+            None => app.status = Some(String::from("Clipboard is empty")),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::config;
+use crate::document::{Container, Segment};
+use crate::schema::Schema;
+use serde_json::Value;
+use std::fs;
+use std::io;
+
+/// 新建成员时可选的模板，key 为 None 时插入前还要输入 key
+pub struct Template {
+    pub name: String,
+    pub key: Option<String>,
+    pub value: Value,
+}
+
+/// 模板选择列表，第 0 行是空白的键值对，模板从第 1 行开始
+pub struct Picker {
+    pub templates: Vec<Template>,
+    pub selected: usize,
+}
+
+impl Picker {
+    pub fn len(&self) -> usize {
+        self.templates.len() + 1
+    }
+
+    /// 选中的模板，选中空白行时为 None
+    pub fn current(&self) -> Option<&Template> {
+        self.selected
+            .checked_sub(1)
+            .and_then(|index| self.templates.get(index))
+    }
+}
+
+/// 配置目录下 `templates/*.json` 中的模板，文件名（不含扩展名）作为模板名
+pub fn from_directory() -> Result<Vec<Template>, String> {
+    let Some(dir) = config::config_dir().map(|dir| dir.join("templates")) else {
+        return Ok(Vec::new());
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("{}: {err}", dir.display())),
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    let mut templates = Vec::new();
+    for path in paths {
+        let content =
+            fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let value =
+            serde_json::from_str(&content).map_err(|err| format!("{}: {err}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        templates.push(Template {
+            name,
+            key: None,
+            value,
+        });
+    }
+    Ok(templates)
+}
+
+/// schema 中当前容器的成员模板：对象中尚未出现的属性，以及 additionalProperties/items 描述的新成员
+pub fn from_schema(schema: &Schema, path: &[Segment], container: &Container) -> Vec<Template> {
+    let Some(current) = schema.at(path) else {
+        return Vec::new();
+    };
+    let mut templates = Vec::new();
+    match container {
+        Container::Object(pairs) => {
+            if let Some(Value::Object(properties)) = current.get("properties") {
+                for (name, property) in properties {
+                    if pairs.contains_key(name) {
+                        continue;
+                    }
+                    for (label, value) in schema.samples(property) {
+                        templates.push(Template {
+                            name: format!("{name} ({label})"),
+                            key: Some(name.clone()),
+                            value,
+                        });
+                    }
+                }
+            }
+            if let Some(entry) = current
+                .get("additionalProperties")
+                .filter(|entry| entry.is_object())
+            {
+                for (label, value) in schema.samples(entry) {
+                    templates.push(Template {
+                        name: format!("new entry ({label})"),
+                        key: None,
+                        value,
+                    });
+                }
+            }
+        }
+        Container::Array(_) => {
+            if let Some(items) = current.get("items").filter(|items| items.is_object()) {
+                for (label, value) in schema.samples(items) {
+                    templates.push(Template {
+                        name: format!("new element ({label})"),
+                        key: None,
+                        value,
+                    });
+                }
+            }
+        }
+    }
+    templates
+}
@@ -1,59 +1,144 @@
-use crate::app::{App, CurrentScreen, CurrentlyEditing};
+use crate::app::{App, CurrentScreen, CurrentlyEditing, Prompt};
+use crate::browser::Browser;
+use crate::stats::Stats;
+use crate::document;
+use crate::lazy::{self, LazyDocument};
+use crate::ndjson::LinesDocument;
+use crate::replace::{Focus, Replace};
+use crate::secrets;
+use crate::tabs::Tabs;
+use crate::templates::Picker;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Styled},
+    style::{Color, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Table, TableState, Tabs as TabBar,
+        Wrap,
+    },
     Frame,
 };
+use std::borrow::Cow;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// 低于该尺寸时只显示 "terminal too small" 提示
+const MIN_WIDTH: u16 = 30;
+const MIN_HEIGHT: u16 = 8;
+/// 低于该尺寸时使用紧凑布局：标题和底栏各占一行
+const COMPACT_WIDTH: u16 = 80;
+const COMPACT_HEIGHT: u16 = 20;
+/// 弹窗宽度低于该值时 Key/Value 输入框上下堆叠
+const STACK_WIDTH: u16 = 50;
+
+pub fn ui(f: &mut Frame, tabs: &Tabs) {
+    let app = tabs.current();
+    let size = f.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        render_too_small(f);
+        return;
+    }
+    let compact = size.width < COMPACT_WIDTH || size.height < COMPACT_HEIGHT;
+    // 紧凑布局下去掉边框，标题和底栏各只占一行
+    let (bar_height, bar_borders) = if compact {
+        (1, Borders::NONE)
+    } else {
+        (3, Borders::ALL)
+    };
 
-pub fn ui(f: &mut Frame, app: &App) {
     // 创建主布局
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
-            Constraint::Max(1),
-            Constraint::Length(3),
+            Constraint::Length(bar_height),
+            Constraint::Min(1),
+            Constraint::Length(bar_height),
         ])
-        .split(f.size());
+        .split(size);
     // 创建标题块样式
     let title_block = Block::default()
-        .borders(Borders::ALL)
+        .borders(bar_borders)
         .style(Style::default());
-    // 创建标题并附加到块中
-    let title = Paragraph::new(Text::styled(
-        "Create New Json",
-        Style::default().fg(Color::Green),
-    ))
-    .block(title_block);
+    // 每个打开的文档一个标签页，有未保存修改的标签以 * 结尾
+    let titles: Vec<String> = tabs
+        .apps
+        .iter()
+        .map(|app| {
+            let marker = if app.is_modified() { "*" } else { "" };
+            format!("{}{marker}", app.title())
+        })
+        .collect();
+    let tab_bar = TabBar::new(titles)
+        .block(title_block)
+        .style(Style::default().fg(Color::DarkGray))
+        .highlight_style(Style::default().fg(Color::Green))
+        .select(tabs.active);
 
     // 绘制标题图形
-    f.render_widget(title, chunks[0]);
+    f.render_widget(tab_bar, chunks[0]);
 
-    let mut list_items = Vec::<ListItem>::new();
-    for key in app.pairs.keys() {
-        list_items.push(ListItem::new(Line::from(Span::styled(
-            format!("{: <25} : {}", key, app.pairs.get(key).unwrap()),
-            Style::default().fg(Color::Yellow),
-        ))));
+    // 文件被外部改写且尚未处理时，在内容区顶部显示一行横幅
+    let content = if app.external.is_some() {
+        let banner_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(chunks[1]);
+        let banner = Paragraph::new(Text::styled(
+            "The file changed on disk: :reload / :merge / :keep",
+            Style::default().fg(Color::Black).bg(Color::LightRed),
+        ));
+        f.render_widget(banner, banner_chunks[0]);
+        banner_chunks[1]
+    } else {
+        chunks[1]
+    };
+    // 选择显示明文之前，所有预览中的机密值都显示为占位符
+    let hidden: &[String] = if app.reveal_secrets { &[] } else { &app.config.secret_patterns };
+    match (&app.lazy, &app.records) {
+        (Some(lazy), _) => render_lazy(f, lazy, content, compact, hidden),
+        (None, Some(records)) if records.open.is_none() => render_records(f, records, content, hidden),
+        _ => render_pairs(f, app, content, compact),
     }
-    let list = List::new(list_items);
-    f.render_widget(list, chunks[1]);
 
     // 为底部导航栏设置文本样式
     let current_navigation_text = vec![
         match app.current_screen {
+            CurrentScreen::Main if app.lazy.is_some() => {
+                Span::styled("Read-only Mode", Style::default().fg(Color::Green))
+            }
             CurrentScreen::Main => Span::styled("Normal Mode", Style::default().fg(Color::Green)),
             CurrentScreen::Editing => {
                 Span::styled("Editing Mode", Style::default().fg(Color::Yellow))
             }
             CurrentScreen::Exiting => Span::styled("Exiting", Style::default().fg(Color::LightRed)),
+            CurrentScreen::Recovering => {
+                Span::styled("Recovering", Style::default().fg(Color::LightRed))
+            }
+            CurrentScreen::Command => Span::styled("Command", Style::default().fg(Color::Yellow)),
+            CurrentScreen::Templates => {
+                Span::styled("Templates", Style::default().fg(Color::Yellow))
+            }
+            CurrentScreen::Open => Span::styled("Open", Style::default().fg(Color::Yellow)),
+            CurrentScreen::Changed => {
+                Span::styled("Changed on disk", Style::default().fg(Color::LightRed))
+            }
+            CurrentScreen::Stats => Span::styled("Statistics", Style::default().fg(Color::Yellow)),
+            CurrentScreen::Replace => Span::styled("Replace", Style::default().fg(Color::Yellow)),
         }
         .to_owned(),
         Span::styled(" | ", Style::default().fg(Color::White)),
+        // 录制宏时一直显示寄存器名
+        match &tabs.macros.recording {
+            Some((register, _)) => Span::styled(
+                format!("recording @{register} | "),
+                Style::default().fg(Color::LightRed),
+            ),
+            None => Span::raw(""),
+        },
         {
-            if let Some(editing) = &app.currently_editing {
+            // 有提示信息时优先显示
+            if let Some(status) = &app.status {
+                Span::styled(status.clone(), Style::default().fg(Color::LightRed))
+            } else if let Some(editing) = &app.currently_editing {
                 match editing {
                     CurrentlyEditing::Key => {
                         Span::styled("Editing Json Key", Style::default().fg(Color::Green))
@@ -67,49 +152,166 @@ pub fn ui(f: &mut Frame, app: &App) {
             }
         },
     ];
-    let mode_footer = Paragraph::new(Line::from(current_navigation_text))
-        .block(Block::default().borders(Borders::ALL));
 
     let current_keys_hint = {
+        let tab_hint = if tabs.apps.len() > 1 {
+            " / (Tab) next tab / (y/p) copy/paste"
+        } else {
+            ""
+        };
         match app.current_screen {
+            CurrentScreen::Main if app.lazy.is_some() => Span::styled(
+                format!("(q) to quit / (↑↓) select / (Enter) open / (Backspace) back{tab_hint}"),
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Main if app.in_record_list() => Span::styled(
+                format!("(q) to quit / (↑↓) select / (Enter) open record{tab_hint}"),
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Main if app.records.is_some() => Span::styled(
+                format!("(Backspace) back to records / (e) to make new pair / (↑↓) select / (u) undo{tab_hint}"),
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Main if app.is_array() => Span::styled(
+                format!("(h/l) out/in / (e) new from template / (a/i/o) append/insert / (D) duplicate / (x) delete / (J/K) move / (s/S) sort / (u) undo{tab_hint}"),
+                Style::default().fg(Color::Red),
+            ),
             CurrentScreen::Main => Span::styled(
-                "(q) to quit / (e) to make new pair",
+                format!("(q) to quit / (e) to make new pair / (↑↓) select / (h/l) out/in / (r) rename / (J/K) move / (x) delete / (m) show secrets / (u) undo / (Ctrl-r) redo / (:) command / (Ctrl-O) open file / (Ctrl-E) $EDITOR / (I) statistics / (R) replace{tab_hint}"),
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Editing if app.prompt.is_some() => Span::styled(
+                "(ESC) to cancel/(Enter) to confirm",
                 Style::default().fg(Color::Red),
             ),
             CurrentScreen::Editing => Span::styled(
-                "(ESC) to cancel/(Tab) to switch boxes/enter to complete",
+                "(ESC) to cancel/(Ctrl-T) to switch boxes/(Tab) complete/(Enter) newline/(Ctrl-E) $EDITOR/(Ctrl-S) to complete",
                 Style::default().fg(Color::Red),
             ),
             CurrentScreen::Exiting => Span::styled(
                 "(q) to quit / (e) to make new pair",
                 Style::default().fg(Color::Red),
             ),
+            CurrentScreen::Recovering => Span::styled(
+                "(y) to restore / (n) to discard",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Command => Span::raw(""),
+            CurrentScreen::Templates => Span::styled(
+                "(↑↓) select / (Enter) use template / (ESC) to cancel",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Open => Span::styled(
+                "(↑↓) select / (Enter/l) open or expand / (h) collapse or go up / (ESC) to cancel",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Changed => Span::styled(
+                "(r) reload / (m) merge / (k) keep mine / (ESC) decide later",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Stats => Span::styled(
+                "(ESC/q) to close",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Replace if matches!(&app.replace, Some(replace) if replace.focus == Focus::Matches) => Span::styled(
+                "(↑↓) select / (Space) accept or reject / (a/n) all/none / (Enter) apply / (ESC) back to pattern",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Replace => Span::styled(
+                "(Tab) switch boxes / (Ctrl-R) regex / (Ctrl-K) scope / (Ctrl-L) range / (Enter) review matches / (ESC) to cancel",
+                Style::default().fg(Color::Red),
+            ),
         }
     };
-    let key_notes_footer =
-        Paragraph::new(Line::from(current_keys_hint)).block(Block::default().borders(Borders::ALL));
 
-    let footer_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(chunks[2]);
+    if let CurrentScreen::Command = app.current_screen {
+        // 命令行替换整个底栏，上次补全的候选项显示在边框标题上
+        let mut block = Block::default().borders(bar_borders);
+        if !compact && !app.completions.is_empty() {
+            block = block.title(app.completions.join(" "));
+        }
+        let command = Paragraph::new(Line::from(vec![
+            Span::styled(":", Style::default().fg(Color::Yellow)),
+            Span::raw(app.command_input.clone()),
+        ]))
+        .block(block);
+        f.render_widget(command, chunks[2]);
+    } else if compact {
+        // 模式和按键提示合并为一行
+        let mut footer_text = current_navigation_text;
+        footer_text.push(Span::styled(" | ", Style::default().fg(Color::White)));
+        footer_text.push(current_keys_hint);
+        f.render_widget(Paragraph::new(Line::from(footer_text)), chunks[2]);
+    } else {
+        let mode_footer = Paragraph::new(Line::from(current_navigation_text))
+            .block(Block::default().borders(Borders::ALL));
+        let key_notes_footer = Paragraph::new(Line::from(current_keys_hint))
+            .block(Block::default().borders(Borders::ALL));
+
+        let footer_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[2]);
 
-    f.render_widget(mode_footer, footer_chunks[0]);
-    f.render_widget(key_notes_footer, footer_chunks[1]);
+        f.render_widget(mode_footer, footer_chunks[0]);
+        f.render_widget(key_notes_footer, footer_chunks[1]);
+    }
 
-    if let Some(editing) = &app.currently_editing {
+    if let Some(prompt) = &app.prompt {
+        // 单字段弹窗：重命名和排序字段只有 Key 框，插入数组元素只有 Value 框
+        let (title, label, input) = match prompt {
+            Prompt::Rename(_) => (String::from("Rename key"), "Key", &app.key_input),
+            Prompt::Insert(index) => (format!("Insert element at [{index}]"), "Value", &app.value_input),
+            Prompt::SortField => (String::from("Sort array by field"), "Field", &app.key_input),
+            Prompt::TemplateKey(_) => (String::from("Key for new entry"), "Key", &app.key_input),
+        };
+        let area = centered_rect(60, 0, MIN_WIDTH - 2, 6, size);
         let popup_block = Block::default()
-            .title("Enter a new key-value pair")
+            .title(title)
             .borders(Borders::NONE)
             .style(Style::default().bg(Color::DarkGray));
-        let area = centered_rect(60, 25, f.size());
         f.render_widget(popup_block, area);
-
+        let input_block = Block::default()
+            .title(label)
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::LightYellow).fg(Color::Black));
         let popup_chunks = Layout::default()
-            .direction(Direction::Horizontal)
             .margin(1)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .constraints([Constraint::Length(3), Constraint::Length(1)])
             .split(area);
+        f.render_widget(Paragraph::new(input.clone()).block(input_block), popup_chunks[0]);
+        f.render_widget(Paragraph::new(escape_preview(input)), popup_chunks[1]);
+    } else if let Some(editing) = &app.currently_editing {
+        let popup_block = Block::default()
+            .title("Enter a new key-value pair")
+            .borders(Borders::NONE)
+            .style(Style::default().bg(Color::DarkGray));
+        // 堆叠时至少需要两个三行的输入框、一行预览加上下边距
+        let area = centered_rect(60, 25, MIN_WIDTH - 2, 9, size);
+        f.render_widget(popup_block, area);
+        // 最下面一行预览正在编辑的输入框中转义序列解码后的文本
+        let preview_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(area);
+        let active_input = match editing {
+            CurrentlyEditing::Key => &app.key_input,
+            CurrentlyEditing::Value => &app.value_input,
+        };
+        f.render_widget(Paragraph::new(escape_preview(active_input)), preview_chunks[1]);
+
+        let popup_chunks = if area.width < STACK_WIDTH {
+            Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(3)])
+                .split(preview_chunks[0])
+        } else {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(preview_chunks[0])
+        };
         let mut key_block = Block::default().title("Key").borders(Borders::ALL);
         let mut value_block = Block::default().title("Value").borders(Borders::ALL);
         let active_style = Style::default().bg(Color::LightYellow).fg(Color::Black);
@@ -120,8 +322,33 @@ pub fn ui(f: &mut Frame, app: &App) {
         let key_text = Paragraph::new(app.key_input.clone()).block(key_block);
         f.render_widget(key_text, popup_chunks[0]);
 
-        let value_text = Paragraph::new(app.value_input.clone()).block(value_block);
-        f.render_widget(value_text, popup_chunks[1])
+        // Value 框按字符软换行，并根据滚动位置只显示可见的行
+        let inner_width = popup_chunks[1].width.saturating_sub(2) as usize;
+        let inner_height = popup_chunks[1].height.saturating_sub(2) as usize;
+        let value_lines = wrap_text(&app.value_input, inner_width);
+        let top = value_lines
+            .len()
+            .saturating_sub(inner_height)
+            .saturating_sub(app.value_scroll);
+        let value_text = Paragraph::new(value_lines.into_iter().map(Line::from).collect::<Vec<_>>())
+            .block(value_block)
+            .scroll((top as u16, 0));
+        f.render_widget(value_text, popup_chunks[1]);
+
+        // 补全候选以下拉列表显示在正在编辑的输入框下方
+        let active_box = match editing {
+            CurrentlyEditing::Key => popup_chunks[0],
+            CurrentlyEditing::Value => popup_chunks[1],
+        };
+        render_suggestions(f, app, active_box, size);
+    }
+
+    if let (CurrentScreen::Templates, Some(picker)) = (&app.current_screen, &app.picker) {
+        render_picker(f, app, picker, centered_rect(70, 60, MIN_WIDTH - 2, 8, size));
+    }
+
+    if let (CurrentScreen::Open, Some(browser)) = (&app.current_screen, &app.browser) {
+        render_browser(f, browser, centered_rect(80, 70, MIN_WIDTH - 2, 8, size));
     }
 
     if let CurrentScreen::Exiting = app.current_screen {
@@ -131,36 +358,651 @@ pub fn ui(f: &mut Frame, app: &App) {
             .borders(Borders::NONE)
             .style(Style::default().bg(Color::DarkGray));
         let exit_text = Text::styled(
-            "Would you like to output the buffer as json? (y/n)",
+            "Would you like to output the buffer as json? (y/n)\nOr press another letter (a-z) to record a macro into that register.",
             Style::default().fg(Color::Red),
         );
         let exit_paragraph = Paragraph::new(exit_text)
             .block(popup_block)
             .wrap(Wrap { trim: false });
 
-        let area = centered_rect(60, 25, f.size());
+        let area = centered_rect(60, 25, MIN_WIDTH - 2, 4, size);
         f.render_widget(exit_paragraph, area);
     }
+
+    if let (CurrentScreen::Stats, Some(stats)) = (&app.current_screen, &app.stats) {
+        render_stats(f, stats, centered_rect(70, 80, MIN_WIDTH - 2, 8, size));
+    }
+
+    if let (CurrentScreen::Replace, Some(replace)) = (&app.current_screen, &app.replace) {
+        render_replace(f, app, replace, centered_rect(80, 80, MIN_WIDTH - 2, 12, size));
+    }
+
+    if let CurrentScreen::Changed = app.current_screen {
+        f.render_widget(Clear, f.size());
+        let popup_block = Block::default()
+            .title("Changed on disk")
+            .borders(Borders::NONE)
+            .style(Style::default().bg(Color::DarkGray));
+        let changed_text = Text::styled(
+            if app.is_modified() {
+                "Another program changed this file. (r) reload and drop your edits, (m) merge their changes into yours, or (k) keep yours and overwrite on save?"
+            } else {
+                "Another program changed this file. (r) reload it, (m) merge, or (k) keep the version shown here?"
+            },
+            Style::default().fg(Color::Red),
+        );
+        let changed_paragraph = Paragraph::new(changed_text)
+            .block(popup_block)
+            .wrap(Wrap { trim: false });
+
+        let area = centered_rect(60, 25, MIN_WIDTH - 2, 4, size);
+        f.render_widget(changed_paragraph, area);
+    }
+
+    if let (CurrentScreen::Recovering, Some(found)) = (&app.current_screen, &app.recovered) {
+        f.render_widget(Clear, f.size());
+        let popup_block = Block::default()
+            .title("Y/N")
+            .borders(Borders::NONE)
+            .style(Style::default().bg(Color::DarkGray));
+        let age = crate::journal::now().saturating_sub(found.saved_at);
+        let recover_text = Text::styled(
+            format!(
+                "Found an unsaved session from {age}s ago ({} pairs). Restore it? (y/n)",
+                found.pairs.len()
+            ),
+            Style::default().fg(Color::Red),
+        );
+        let recover_paragraph = Paragraph::new(recover_text)
+            .block(popup_block)
+            .wrap(Wrap { trim: false });
+
+        let area = centered_rect(60, 25, MIN_WIDTH - 2, 4, size);
+        f.render_widget(recover_paragraph, area);
+    }
+}
+
+/// 以两列表格绘制键值对，旁边的详情栏显示选中行的完整信息
+fn render_pairs(f: &mut Frame, app: &App, area: Rect, compact: bool) {
+    // 紧凑布局下详情栏缩成表格下方的一行，只显示完整 key
+    let chunks = if compact {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area)
+    } else {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area)
+    };
+
+    let entries = app.entries();
+    // key 中的不可见字符以转义形式显示
+    let labels: Vec<String> = entries
+        .iter()
+        .map(|(key, _)| document::escape_nonprintable(key))
+        .collect();
+    // key 列宽度取最长 key 的显示宽度，但最多占表格的 40%，超出部分以省略号截断；数组显示下标
+    let longest = labels.iter().map(|label| label.width()).max().unwrap_or(0) as u16;
+    let key_width = longest.min(chunks[0].width * 2 / 5).max(1);
+    let rows = entries.iter().zip(&labels).map(|((key, value), label)| {
+        Row::new(vec![
+            Cell::from(truncate(label, key_width as usize)),
+            Cell::from(":"),
+            Cell::from(document::display_value(&app.shown_value(key, value))),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(key_width),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ],
+    )
+    .style(Style::default().fg(Color::Yellow))
+    .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+    .highlight_symbol(">> ");
+    // 进入嵌套容器后在表格上方显示当前路径
+    let table = if app.path.is_empty() {
+        table
+    } else {
+        table.block(Block::default().title(document::json_path(&app.path)))
+    };
+    let mut state = TableState::default().with_selected(app.selected());
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let Some((key, value)) = app.selected_pair() else {
+        let empty = Paragraph::new(Text::styled(
+            if app.is_array() { "Empty array" } else { "No pairs yet" },
+            Style::default().fg(Color::DarkGray),
+        ));
+        f.render_widget(empty, chunks[1]);
+        return;
+    };
+    let label = document::escape_nonprintable(&key);
+    if compact {
+        let detail = Paragraph::new(Text::styled(label, Style::default().fg(Color::Yellow)));
+        f.render_widget(detail, chunks[1]);
+        return;
+    }
+
+    let label_style = Style::default().fg(Color::DarkGray);
+    let key_label = if app.is_array() { "Index: " } else { "Key:  " };
+    let mut detail_lines = vec![
+        Line::from(vec![Span::styled(key_label, label_style), Span::raw(label)]),
+        Line::from(vec![
+            Span::styled("Path: ", label_style),
+            Span::raw(document::json_path(&app.selected_path())),
+        ]),
+        Line::from(vec![
+            Span::styled("Type: ", label_style),
+            Span::raw(document::type_name(value)),
+        ]),
+        Line::from(vec![
+            Span::styled("Size: ", label_style),
+            Span::raw(format!("{} bytes", document::byte_size(value))),
+        ]),
+        Line::from(""),
+    ];
+    detail_lines.extend(
+        document::preview_value(&app.shown_value(&key, value))
+            .lines()
+            .map(|line| Line::styled(line.to_string(), Style::default().fg(Color::Yellow))),
+    );
+    let detail = Paragraph::new(detail_lines)
+        .block(Block::default().title("Detail").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, chunks[1]);
+}
+
+/// 补全下拉列表，最多显示 6 项，放不下时截到屏幕底部
+fn render_suggestions(f: &mut Frame, app: &App, anchor: Rect, size: Rect) {
+    let suggestions = app.suggestions();
+    let top = anchor.y + anchor.height;
+    let height = (suggestions.len() as u16 + 2)
+        .min(8)
+        .min(size.height.saturating_sub(top));
+    if suggestions.is_empty() || height < 3 {
+        return;
+    }
+    let area = Rect::new(anchor.x, top, anchor.width, height);
+    f.render_widget(Clear, area);
+    let rows = suggestions
+        .iter()
+        .map(|(text, _)| Row::new(vec![Cell::from(text.clone())]));
+    let table = Table::new(rows, [Constraint::Min(1)])
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::Yellow).bg(Color::Black))
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+    let selected = app.suggestion % suggestions.len();
+    let mut state = TableState::default().with_selected(Some(selected));
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+/// 文档统计浮层：左列为节点数、深度和大小，右列为各项排行
+fn render_stats(f: &mut Frame, stats: &Stats, area: Rect) {
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title("Statistics")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(inner);
+
+    let heading = Style::default().fg(Color::Yellow);
+    let total: usize = stats.counts.iter().map(|(_, count)| count).sum();
+    let mut summary = vec![Line::styled(format!("Nodes: {total}"), heading)];
+    summary.extend(
+        stats
+            .counts
+            .iter()
+            .map(|(kind, count)| Line::from(format!("  {kind:<8} {count}"))),
+    );
+    summary.push(Line::from(""));
+    summary.push(Line::styled(format!("Max depth: {}", stats.max_depth), heading));
+    summary.push(Line::from(""));
+    summary.push(Line::styled("Serialized size", heading));
+    summary.push(Line::from(format!("  compact  {} bytes", stats.compact)));
+    summary.push(Line::from(format!("  pretty   {} bytes", stats.pretty)));
+    f.render_widget(Paragraph::new(summary), chunks[0]);
+
+    // 排行榜：标题加上若干“名称  数量”行，没有条目时显示 none
+    let mut ranking = Vec::new();
+    let sections = [
+        ("Largest objects/arrays", &stats.largest, "members"),
+        ("Longest strings", &stats.longest, "chars"),
+        ("Duplicate values", &stats.duplicates, "times"),
+    ];
+    for (title, entries, unit) in sections {
+        if !ranking.is_empty() {
+            ranking.push(Line::from(""));
+        }
+        ranking.push(Line::styled(title, heading));
+        if entries.is_empty() {
+            ranking.push(Line::styled("  none", Style::default().fg(Color::Gray)));
+        }
+        for (label, count) in entries {
+            ranking.push(Line::from(format!("  {count:>6} {unit:<7} {label}")));
+        }
+    }
+    let ranking = Paragraph::new(ranking)
+        .block(Block::default().borders(Borders::LEFT))
+        .wrap(Wrap { trim: false });
+    f.render_widget(ranking, chunks[1]);
 }
 
-/// 创建剧中矩形
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    // 将给定的矩形切割成三个垂直部分
-    let popup_layout = Layout::default()
+/// 查找替换对话框：上方为查找和替换框及选项，下方列出所有匹配项及替换前后的文本
+fn render_replace(f: &mut Frame, app: &App, replace: &Replace, area: Rect) {
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title("Replace")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Percentage(100 - percent_y / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage(100 - percent_y / 2),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
         ])
-        .split(r);
-    // 中间部分切割成三个部分
-    Layout::default()
+        .split(inner);
+
+    let boxes = [
+        ("Find", &replace.find, Focus::Find, chunks[0]),
+        ("Replace with", &replace.replacement, Focus::Replacement, chunks[1]),
+    ];
+    for (title, text, focus, area) in boxes {
+        let mut block = Block::default().title(title).borders(Borders::ALL);
+        if replace.focus == focus {
+            block = block.style(Style::default().bg(Color::LightYellow).fg(Color::Black));
+        }
+        f.render_widget(Paragraph::new(text.clone()).block(block), area);
+    }
+
+    let range = if replace.whole_document || app.path.is_empty() {
+        String::from("whole document")
+    } else {
+        document::json_path(&app.path)
+    };
+    let options = Line::from(vec![
+        Span::styled(
+            if replace.regex { "regex" } else { "literal" },
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::raw(" (Ctrl-R) | "),
+        Span::styled(replace.scope.label(), Style::default().fg(Color::Yellow)),
+        Span::raw(" (Ctrl-K) | "),
+        Span::styled(range, Style::default().fg(Color::Yellow)),
+        Span::raw(" (Ctrl-L)"),
+    ]);
+    f.render_widget(Paragraph::new(options), chunks[2]);
+
+    // 机密值不以明文出现在预览中
+    let patterns = &app.config.secret_patterns;
+    let rows = replace.matches.iter().map(|found| {
+        let secret = !app.reveal_secrets
+            && !found.is_key
+            && found.path.iter().any(|segment| {
+                matches!(segment, document::Segment::Key(key) if secrets::is_secret(key, patterns))
+            });
+        let (before, after) = if secret {
+            (secrets::MASK.to_string(), secrets::MASK.to_string())
+        } else {
+            (
+                document::escape_nonprintable(&found.before),
+                document::escape_nonprintable(&found.after),
+            )
+        };
+        let path = document::json_path(&found.path);
+        Row::new(vec![
+            Cell::from(if found.accepted { "[x]" } else { "[ ]" }),
+            Cell::from(if found.is_key { format!("{path} (key)") } else { path }),
+            Cell::from(format!("{before} → {after}")),
+        ])
+    });
+    let mut table = Table::new(
+        rows,
+        [Constraint::Length(3), Constraint::Percentage(40), Constraint::Percentage(60)],
+    )
+    .style(Style::default().fg(Color::Yellow))
+    .block(Block::default().borders(Borders::TOP));
+    if replace.focus == Focus::Matches {
+        table = table
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            .highlight_symbol(">> ");
+    }
+    let selected = (!replace.matches.is_empty()).then_some(replace.selected);
+    let mut state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, chunks[3], &mut state);
+
+    let footer = match &replace.error {
+        Some(error) => Line::styled(
+            error.lines().last().unwrap_or_default().to_string(),
+            Style::default().fg(Color::Red),
+        ),
+        None if replace.find.is_empty() => Line::from("Type a pattern to find"),
+        None => Line::from(format!(
+            "{} of {} selected",
+            replace.accepted(),
+            replace.matches.len()
+        )),
+    };
+    f.render_widget(Paragraph::new(footer), chunks[4]);
+}
+
+/// 文件浏览器：左侧为目录树，右侧预览选中的文件
+fn render_browser(f: &mut Frame, browser: &Browser, area: Rect) {
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title(format!("Open {}", browser.root.display()))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(100 - percent_x / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage(100 - percent_x / 2),
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(inner);
+
+    let rows = browser.entries.iter().map(|entry| {
+        let marker = match (entry.is_dir, entry.expanded) {
+            (true, true) => "▾ ",
+            (true, false) => "▸ ",
+            (false, _) => "  ",
+        };
+        let indent = "  ".repeat(entry.depth);
+        Row::new(vec![Cell::from(format!("{indent}{marker}{}", entry.name()))])
+    });
+    let table = Table::new(rows, [Constraint::Min(1)])
+        .style(Style::default().fg(Color::Yellow))
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        .highlight_symbol(">> ");
+    let selected = (!browser.entries.is_empty()).then_some(browser.selected);
+    let mut state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let preview = Paragraph::new(Text::styled(
+        browser.preview.as_str(),
+        Style::default().fg(Color::Yellow),
+    ))
+    .block(Block::default().borders(Borders::LEFT))
+    .wrap(Wrap { trim: false });
+    f.render_widget(preview, chunks[1]);
+}
+
+/// 模板选择弹窗：左侧为模板名，右侧预览选中模板的值
+fn render_picker(f: &mut Frame, app: &App, picker: &Picker, area: Rect) {
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title("New entry from template")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(inner);
+
+    let names = std::iter::once(String::from("(blank)"))
+        .chain(picker.templates.iter().map(|template| template.name.clone()));
+    let table = Table::new(names.map(|name| Row::new(vec![Cell::from(name)])), [Constraint::Min(1)])
+        .style(Style::default().fg(Color::Yellow))
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        .highlight_symbol(">> ");
+    let mut state = TableState::default().with_selected(Some(picker.selected));
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let preview = match picker.current() {
+        Some(template) => {
+            let key = template.key.as_deref().unwrap_or_default();
+            let shown = app.shown_value(key, &template.value);
+            serde_json::to_string_pretty(shown.as_ref()).unwrap_or_default()
+        }
+        None => String::from("Type the key and value yourself"),
+    };
+    let preview = Paragraph::new(Text::styled(preview, Style::default().fg(Color::Yellow)))
+        .block(Block::default().borders(Borders::LEFT))
+        .wrap(Wrap { trim: false });
+    f.render_widget(preview, chunks[1]);
+}
+
+/// JSON Lines 模式的记录列表，左侧为行号，修改过的行以 * 标记
+fn render_records(f: &mut Frame, records: &LinesDocument, area: Rect, hidden: &[String]) {
+    let number_width = records.len().max(1).to_string().len() as u16 + 1;
+    let rows = (0..records.len()).map(|index| {
+        let marker = if records.is_edited(index) { "*" } else { "" };
+        Row::new(vec![
+            Cell::from(format!("{}{marker}", index + 1)),
+            Cell::from(secrets::mask_text(&records.preview(index), hidden).into_owned()),
         ])
-        .split(popup_layout[1])[1]
+    });
+    let table = Table::new(rows, [Constraint::Length(number_width), Constraint::Min(1)])
+        .style(Style::default().fg(Color::Yellow))
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        .highlight_symbol(">> ");
+    let selected = (records.len() > 0).then_some(records.selected);
+    let mut state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+/// 延迟加载模式：只绘制可见窗口中的成员，索引期间显示进度条
+fn render_lazy(f: &mut Frame, lazy: &LazyDocument, area: Rect, compact: bool, hidden: &[String]) {
+    if let Some((ratio, label)) = lazy.progress() {
+        let gauge = Gauge::default()
+            .block(Block::default().title(format!("Indexing {label}")).borders(Borders::ALL))
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(ratio);
+        f.render_widget(gauge, centered_rect(60, 0, MIN_WIDTH - 2, 3, area));
+        return;
+    }
+    if let Some(error) = &lazy.error {
+        let text = Text::styled(format!("Failed to index: {error}"), Style::default().fg(Color::Red));
+        f.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), area);
+        return;
+    }
+
+    let chunks = if compact {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area)
+    } else {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area)
+    };
+
+    // 所在的容器本身是机密值时其中的内容都隐藏
+    let inside_secret = lazy.keys().any(|key| secrets::is_secret(key, hidden));
+    let mut selected_secret = inside_secret;
+    if let Some(level) = lazy.current() {
+        let (top, rows) = match lazy.visible_rows(chunks[0].height as usize) {
+            Ok(window) => window,
+            Err(err) => {
+                let text = Text::styled(err.to_string(), Style::default().fg(Color::Red));
+                f.render_widget(Paragraph::new(text), chunks[0]);
+                return;
+            }
+        };
+        let longest = rows.iter().map(|row| row.key.width()).max().unwrap_or(0) as u16;
+        let key_width = longest.min(chunks[0].width * 2 / 5).max(1);
+        let is_secret = |row: &lazy::Row| {
+            inside_secret || (level.is_object && secrets::is_secret(&row.key, hidden))
+        };
+        selected_secret = rows.get(level.selected - top).is_some_and(is_secret);
+        let table_rows = rows.iter().map(|row| {
+            let preview = if is_secret(row) {
+                secrets::MASK.to_string()
+            } else {
+                secrets::mask_text(&row.preview, hidden).into_owned()
+            };
+            Row::new(vec![
+                Cell::from(truncate(&row.key, key_width as usize)),
+                Cell::from(":"),
+                Cell::from(preview),
+            ])
+        });
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Length(key_width),
+                Constraint::Length(1),
+                Constraint::Min(1),
+            ],
+        )
+        .style(Style::default().fg(Color::Yellow))
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        .highlight_symbol(">> ");
+        let mut state = TableState::default().with_selected(Some(level.selected - top));
+        f.render_stateful_widget(table, chunks[0], &mut state);
+    }
+
+    let path = match lazy.current() {
+        Some(level) => lazy.path_of(level.selected),
+        None => String::from("$"),
+    };
+    if compact {
+        let detail = Paragraph::new(Text::styled(path, Style::default().fg(Color::Yellow)));
+        f.render_widget(detail, chunks[1]);
+        return;
+    }
+    let label_style = Style::default().fg(Color::DarkGray);
+    let mut detail_lines = vec![Line::from(vec![
+        Span::styled("Path: ", label_style),
+        Span::raw(path),
+    ])];
+    match lazy.detail() {
+        Ok(Some((kind, size, text))) => {
+            detail_lines.push(Line::from(vec![
+                Span::styled("Type: ", label_style),
+                Span::raw(kind),
+            ]));
+            detail_lines.push(Line::from(vec![
+                Span::styled("Size: ", label_style),
+                Span::raw(format!("{size} bytes")),
+            ]));
+            detail_lines.push(Line::from(""));
+            let text = if selected_secret {
+                Cow::Borrowed(secrets::MASK)
+            } else {
+                secrets::mask_text(&text, hidden)
+            };
+            detail_lines.extend(
+                text.lines()
+                    .map(|line| Line::styled(line.to_string(), Style::default().fg(Color::Yellow))),
+            );
+        }
+        Ok(None) => {}
+        Err(err) => detail_lines.push(Line::styled(err.to_string(), Style::default().fg(Color::Red))),
+    }
+    let detail = Paragraph::new(detail_lines)
+        .block(Block::default().title("Detail").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, chunks[1]);
+}
+
+/// 输入中含有转义序列时的预览行：显示解码后的文本，换行和制表符用符号表示，转义有误时显示原因
+fn escape_preview(input: &str) -> Line<'static> {
+    if !input.contains('\\') {
+        return Line::from("");
+    }
+    match document::unescape(input) {
+        Ok(text) => {
+            let shown = text
+                .split('\n')
+                .map(|line| {
+                    line.split('\t')
+                        .map(document::escape_nonprintable)
+                        .collect::<Vec<_>>()
+                        .join("⇥")
+                })
+                .collect::<Vec<_>>()
+                .join("↵");
+            Line::from(vec![
+                Span::styled("Preview: ", Style::default().fg(Color::Gray)),
+                Span::styled(shown, Style::default().fg(Color::White)),
+            ])
+        }
+        Err(err) => Line::styled(err, Style::default().fg(Color::LightRed)),
+    }
+}
+
+/// 按显示宽度截断文本，超出时以省略号结尾
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// 创建居中矩形，按百分比计算尺寸，但不小于给定的最小宽高（也不超出 r）
+fn centered_rect(percent_x: u16, percent_y: u16, min_width: u16, min_height: u16, r: Rect) -> Rect {
+    let width = ((r.width as u32 * percent_x as u32 / 100) as u16)
+        .max(min_width)
+        .min(r.width);
+    let height = ((r.height as u32 * percent_y as u32 / 100) as u16)
+        .max(min_height)
+        .min(r.height);
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// 终端尺寸不足时的提示屏幕
+fn render_too_small(f: &mut Frame) {
+    let size = f.size();
+    let text = Text::styled(
+        format!(
+            "Terminal too small: {}x{} (need at least {MIN_WIDTH}x{MIN_HEIGHT})",
+            size.width, size.height
+        ),
+        Style::default().fg(Color::Red),
+    );
+    f.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), size);
+}
+
+/// 按显示宽度把文本切成不超过 width 的行，保留原有的换行
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for raw_line in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for c in raw_line.chars() {
+            let w = c.width().unwrap_or(0);
+            if line_width + w > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            line.push(c);
+            line_width += w;
+        }
+        lines.push(line);
+    }
+    lines
 }
@@ -0,0 +1,41 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+/// 监听正在编辑的文件被其他进程改写
+/// 监听所在目录而不是文件本身，这样先写临时文件再改名替换的写法也能收到通知
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    path: PathBuf,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> notify::Result<FileWatcher> {
+        // 文件可能还不存在，只规范化所在目录；通知中的路径以监听的目录为前缀
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let dir = fs::canonicalize(dir)?;
+        let path = dir.join(path.file_name().unwrap_or_default());
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        Ok(FileWatcher {
+            _watcher: watcher,
+            events,
+            path,
+        })
+    }
+
+    /// 取出积累的通知，其中有涉及该文件的事件时返回 true
+    pub fn touched(&self) -> bool {
+        let mut touched = false;
+        for event in self.events.try_iter().flatten() {
+            touched |= event.paths.contains(&self.path);
+        }
+        touched
+    }
+}